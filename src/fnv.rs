@@ -0,0 +1,31 @@
+//! A minimal FNV-1a hasher.
+//!
+//! Unlike the standard library's `DefaultHasher`, this has no random seed
+//! and a fixed algorithm, so it gives the same hash for the same input on every run.
+
+use core::hash::Hasher;
+
+const OFFSET_BASIS: u64 = 0xCBF2_9CE4_8422_2325;
+const PRIME: u64 = 0x0000_0100_0000_01B3;
+
+/// A 64-bit FNV-1a hasher.
+pub(crate) struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Fnv1a(OFFSET_BASIS)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(PRIME);
+        }
+    }
+}
@@ -1,7 +1,9 @@
-use crate::{Layer, Property, SubLayer, Tile, TileMap, TileSet};
+use crate::prelude::*;
+use crate::{Layer, Property, SubLayer, Tile, TileMap, TileMapStats, TileSet};
+use alloc::collections::BTreeMap;
+use core::fmt;
+use core::fmt::{Display, Formatter, Write};
 use fmt::Debug;
-use std::fmt;
-use std::fmt::{Display, Formatter, Write};
 
 impl Debug for Layer {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
@@ -41,6 +43,7 @@ impl Debug for Layer {
             writeln!(buf, "tile_dimensions: {:?},", self.tile_dimensions)?;
             writeln!(buf, "sublayer_link: {:?},", self.sublayer_link)?;
             writeln!(buf, "sublayers: {:#?},", self.sublayers)?;
+            writeln!(buf, "default_tile: {:?},", self.default_tile)?;
             // Pad lines
             for line in buf.lines() {
                 writeln!(f, "    {line}")?;
@@ -60,7 +63,8 @@ impl Debug for Layer {
             write!(f, "opacity: {:?}, ", self.opacity)?;
             write!(f, "tile_dimensions: {:?}, ", self.tile_dimensions)?;
             write!(f, "sublayer_link: {:?}, ", self.sublayer_link)?;
-            write!(f, "sublayers: {:?} }}", self.sublayers)
+            write!(f, "sublayers: {:?}, ", self.sublayers)?;
+            write!(f, "default_tile: {:?} }}", self.default_tile)
         }
     }
 }
@@ -69,12 +73,13 @@ impl Debug for Property {
     fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
         match self {
             Property::Integer(i) => write!(fmt, "{i}"),
+            Property::Boolean(b) => write!(fmt, "{b}"),
             Property::Float(f) => write!(fmt, "{f:?}"),
             Property::String(b) => {
                 let escaped = b
                     .iter()
                     .copied()
-                    .flat_map(std::ascii::escape_default)
+                    .flat_map(core::ascii::escape_default)
                     .collect::<Vec<u8>>();
                 write!(fmt, "\"{}\"", unsafe {
                     // SAFETY: escape_default always
@@ -104,6 +109,16 @@ impl Debug for Tile {
     }
 }
 
+/// Formats a tile with both its ID and its position, returned by [`Tile::debug_position`].
+pub(crate) struct TilePosition(pub(crate) Tile);
+
+impl Debug for TilePosition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let (x, y) = self.0.tile_coords();
+        write!(f, "{:04X} (pos [{x}, {y}])", self.0.id())
+    }
+}
+
 impl Debug for SubLayer {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         if f.alternate() {
@@ -158,9 +173,29 @@ impl Debug for TileMap {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         writeln!(f, "TileMap {{")?;
         let mut buf = String::new();
+        writeln!(buf, "version: {:?},", self.version)?;
+        writeln!(buf, "version_flags: {:?},", self.version_flags)?;
         writeln!(buf, "layers: {:#?},", self.layers)?;
         writeln!(buf, "tilesets: {:#?},", self.tilesets)?;
-        writeln!(buf, "properties: {:#?}", self.properties)?;
+        if f.alternate() {
+            // Sort the maps so that the output is the same every time
+            let properties = self
+                .properties_sorted()
+                .into_iter()
+                .collect::<BTreeMap<_, _>>();
+            writeln!(buf, "properties: {properties:#?},")?;
+        } else {
+            writeln!(buf, "properties: {:#?},", self.properties)?;
+        }
+        writeln!(buf, "unknown_blocks: {:02X?},", self.unknown_blocks)?;
+        writeln!(buf, "framing: {:?},", self.framing)?;
+        if f.alternate() {
+            let lossy_strings = self.lossy_strings.iter().collect::<BTreeMap<_, _>>();
+            writeln!(buf, "lossy_strings: {lossy_strings:?},")?;
+        } else {
+            writeln!(buf, "lossy_strings: {:?},", self.lossy_strings)?;
+        }
+        writeln!(buf, "empty_tile: {:?}", self.empty_tile)?;
         // Pad lines
         for line in buf.lines() {
             writeln!(f, "    {line}")?;
@@ -189,3 +224,83 @@ impl Debug for TileSet {
         }
     }
 }
+
+/// Returns the suffix for a count of something.
+fn plural(count: usize) -> &'static str {
+    if count == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
+
+impl Display for TileMap {
+    /// Writes a short summary of the tilemap,
+    /// with the dimensions of its largest layer.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let (width, height) = self
+            .layers
+            .iter()
+            .map(|layer| (layer.width, layer.height))
+            .max_by_key(|&(width, height)| width as u64 * height as u64)
+            .unwrap_or((0, 0));
+        let (layers, tilesets, properties) = (
+            self.layers.len(),
+            self.tilesets.len(),
+            self.properties.len(),
+        );
+        write!(
+            f,
+            "TileMap({layers} layer{}, {tilesets} tileset{}, {properties} propert{}, {width}x{height})",
+            plural(layers),
+            plural(tilesets),
+            if properties == 1 { "y" } else { "ies" },
+        )
+    }
+}
+
+impl Display for TileMapStats {
+    /// Writes the stats on one line.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} layer{}, {} tileset{}, {} propert{}, {} tile{} ({} non-empty), {} sublayer byte{}, bounds {}x{}",
+            self.layers,
+            plural(self.layers),
+            self.tilesets,
+            plural(self.tilesets),
+            self.properties,
+            if self.properties == 1 { "y" } else { "ies" },
+            self.tiles,
+            plural(self.tiles),
+            self.non_empty_tiles,
+            self.sublayer_bytes,
+            plural(self.sublayer_bytes),
+            self.bounds.0,
+            self.bounds.1,
+        )
+    }
+}
+
+impl Display for Layer {
+    /// Writes a short summary of the layer.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let sublayers = self.sublayers.len();
+        write!(
+            f,
+            "Layer {}x{}, tileset {}, {sublayers} sublayer{}",
+            self.width,
+            self.height,
+            self.tileset,
+            plural(sublayers)
+        )
+    }
+}
+
+impl Display for TileSet {
+    /// Writes the path and transparent color of the tileset.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let (r, g, b) = self.transparent_color;
+        write!(f, "{} #{r:02X}{g:02X}{b:02X}", self.path)
+    }
+}
@@ -0,0 +1,187 @@
+//! Helpers for manipulating row-major grids of cells,
+//! shared between the tiles of a layer and the bytes of a sublayer.
+//!
+//! Each cell of a grid is `cell` elements long, so a row is `width * cell` elements.
+
+use crate::prelude::*;
+
+/// Reverse the order of the cells in each row.
+pub(crate) fn flip_horizontal<T>(data: &mut [T], width: usize, cell: usize) {
+    if width == 0 || cell == 0 {
+        return;
+    }
+    for row in data.chunks_exact_mut(width * cell) {
+        // Reversing the whole row also reverses the elements within each cell,
+        // so those get reversed back afterwards
+        row.reverse();
+        for cell in row.chunks_exact_mut(cell) {
+            cell.reverse();
+        }
+    }
+}
+
+/// Reverse the order of the rows.
+pub(crate) fn flip_vertical<T>(data: &mut [T], width: usize, cell: usize) {
+    let stride = width * cell;
+    if stride == 0 {
+        return;
+    }
+    let height = data.len() / stride;
+    for y in 0..height / 2 {
+        let (top, bottom) = data.split_at_mut((height - 1 - y) * stride);
+        top[y * stride..(y + 1) * stride].swap_with_slice(&mut bottom[..stride]);
+    }
+}
+
+/// Rotate the grid clockwise, returning the new data.
+/// The rotated grid is `height` cells wide and `width` cells tall.
+pub(crate) fn rotate_cw<T: Copy>(data: &[T], width: usize, height: usize, cell: usize) -> Vec<T> {
+    // The length of the data is already the area, so this can't overflow
+    let mut rotated = Vec::with_capacity(data.len());
+    for new_y in 0..width {
+        for new_x in 0..height {
+            let (x, y) = (new_y, height - 1 - new_x);
+            let start = (y * width + x) * cell;
+            rotated.extend_from_slice(&data[start..start + cell]);
+        }
+    }
+    rotated
+}
+
+/// Rotate the grid counterclockwise, returning the new data.
+/// The rotated grid is `height` cells wide and `width` cells tall.
+pub(crate) fn rotate_ccw<T: Copy>(data: &[T], width: usize, height: usize, cell: usize) -> Vec<T> {
+    let mut rotated = Vec::with_capacity(data.len());
+    for new_y in 0..width {
+        for new_x in 0..height {
+            let (x, y) = (width - 1 - new_y, new_x);
+            let start = (y * width + x) * cell;
+            rotated.extend_from_slice(&data[start..start + cell]);
+        }
+    }
+    rotated
+}
+
+/// Copy a `w` by `h` region of cells, starting at `(x, y)`, into a new grid.
+/// The region must be in bounds.
+pub(crate) fn crop<T: Copy>(
+    data: &[T],
+    width: usize,
+    cell: usize,
+    (x, y, w, h): (usize, usize, usize, usize),
+) -> Vec<T> {
+    let mut cropped = Vec::with_capacity(w * h * cell);
+    for row in y..y + h {
+        let start = (row * width + x) * cell;
+        cropped.extend_from_slice(&data[start..start + w * cell]);
+    }
+    cropped
+}
+
+/// Copy a row-major grid of `w` by `h` cells into the grid, starting at `(x, y)`.
+/// The region must be in bounds, and the source must be exactly as big as it.
+pub(crate) fn paste<T: Copy>(
+    data: &mut [T],
+    width: usize,
+    cell: usize,
+    (x, y, w, h): (usize, usize, usize, usize),
+    src: &[T],
+) {
+    if w == 0 {
+        return;
+    }
+    for (row, src_row) in (y..y + h).zip(src.chunks_exact(w * cell)) {
+        let start = (row * width + x) * cell;
+        data[start..start + w * cell].copy_from_slice(src_row);
+    }
+}
+
+/// Check that a region lies within a grid, returning it in `usize`s if it does.
+pub(crate) fn region(
+    (width, height): (u32, u32),
+    (x, y, w, h): (u32, u32, u32, u32),
+) -> Option<(usize, usize, usize, usize)> {
+    if x.checked_add(w)? > width || y.checked_add(h)? > height {
+        return None;
+    }
+    Some((x as usize, y as usize, w as usize, h as usize))
+}
+
+/// Copy a grid into a new grid of a different size, filling new cells with `fill`,
+/// which is one cell long.
+///
+/// The `anchor` says how far along each axis the part of the grid that stays in place is, in halves,
+/// so `(0, 0)` keeps the top left corner in place, and `(1, 1)` keeps the center in place.
+pub(crate) fn resize_anchored<T: Copy>(
+    data: &[T],
+    (width, height): (usize, usize),
+    (new_width, new_height): (usize, usize),
+    (anchor_x, anchor_y): (usize, usize),
+    fill: &[T],
+) -> Vec<T> {
+    let cell = fill.len();
+    let (src_x, dest_x, w) = overlap(width, new_width, anchor_x);
+    let (src_y, dest_y, h) = overlap(height, new_height, anchor_y);
+    let mut resized = fill.repeat(new_width * new_height);
+    for row in 0..h {
+        let src = ((src_y + row) * width + src_x) * cell;
+        let dest = ((dest_y + row) * new_width + dest_x) * cell;
+        resized[dest..dest + w * cell].copy_from_slice(&data[src..src + w * cell]);
+    }
+    resized
+}
+
+/// Move every cell of a grid by `(dx, dy)` cells, returning the new data.
+///
+/// With `wrap`, cells that move off one edge come back in on the opposite edge.
+/// Otherwise they're dropped, and the cells left behind are filled with `fill`, which is one cell long.
+// The width and height come from u32s, so they always fit in an i64
+#[allow(clippy::cast_possible_wrap)]
+pub(crate) fn shift<T: Copy>(
+    data: &[T],
+    (width, height): (usize, usize),
+    (dx, dy): (i64, i64),
+    wrap: bool,
+    fill: &[T],
+) -> Vec<T> {
+    let cell = fill.len();
+    let mut shifted = fill.repeat(width * height);
+    if width == 0 || height == 0 {
+        return shifted;
+    }
+    // Find where each cell of the new grid came from
+    let source = |new: usize, delta: i64, len: usize| {
+        if wrap {
+            // Reduce the delta first so that huge ones can't overflow
+            let delta = delta.rem_euclid(len as i64) as usize;
+            Some((new + len - delta) % len)
+        } else {
+            let old = (new as i64).checked_sub(delta)?;
+            usize::try_from(old).ok().filter(|&old| old < len)
+        }
+    };
+    for y in 0..height {
+        let Some(src_y) = source(y, dy, height) else {
+            continue;
+        };
+        for x in 0..width {
+            let Some(src_x) = source(x, dx, width) else {
+                continue;
+            };
+            let src = (src_y * width + src_x) * cell;
+            let dest = (y * width + x) * cell;
+            shifted[dest..dest + cell].copy_from_slice(&data[src..src + cell]);
+        }
+    }
+    shifted
+}
+
+/// Find where a resized axis overlaps with the old one,
+/// returning the start in the old axis, the start in the new axis, and the length.
+fn overlap(old: usize, new: usize, anchor: usize) -> (usize, usize, usize) {
+    if new >= old {
+        (0, (new - old) * anchor / 2, old)
+    } else {
+        ((old - new) * anchor / 2, 0, new)
+    }
+}
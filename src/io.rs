@@ -0,0 +1,87 @@
+//! The IO traits the crate reads and writes through.
+//!
+//! With the `std` feature, these are the ones from `std::io`.
+//! Without it, they're the ones from `no_std_io2`, along with stand-ins
+//! for byteorder's extension traits, which only exist for `std::io`.
+
+pub(crate) use no_std_io2::io::*;
+
+#[cfg(feature = "std")]
+pub(crate) use byteorder::{ReadBytesExt, WriteBytesExt};
+
+#[cfg(not(feature = "std"))]
+pub(crate) use no_std_ext::{ReadBytesExt, WriteBytesExt};
+
+#[cfg(not(feature = "std"))]
+mod no_std_ext {
+    use super::{Read, Result, Write};
+    use byteorder::ByteOrder;
+
+    /// Reads numbers from a readable, like byteorder's trait of the same name.
+    pub(crate) trait ReadBytesExt: Read {
+        fn read_u8(&mut self) -> Result<u8> {
+            let mut buf = [0; 1];
+            self.read_exact(&mut buf)?;
+            Ok(buf[0])
+        }
+
+        fn read_u16<T: ByteOrder>(&mut self) -> Result<u16> {
+            let mut buf = [0; 2];
+            self.read_exact(&mut buf)?;
+            Ok(T::read_u16(&buf))
+        }
+
+        fn read_u32<T: ByteOrder>(&mut self) -> Result<u32> {
+            let mut buf = [0; 4];
+            self.read_exact(&mut buf)?;
+            Ok(T::read_u32(&buf))
+        }
+
+        fn read_i32<T: ByteOrder>(&mut self) -> Result<i32> {
+            let mut buf = [0; 4];
+            self.read_exact(&mut buf)?;
+            Ok(T::read_i32(&buf))
+        }
+
+        fn read_f32<T: ByteOrder>(&mut self) -> Result<f32> {
+            let mut buf = [0; 4];
+            self.read_exact(&mut buf)?;
+            Ok(T::read_f32(&buf))
+        }
+    }
+
+    impl<R: Read + ?Sized> ReadBytesExt for R {}
+
+    /// Writes numbers to a writable, like byteorder's trait of the same name.
+    pub(crate) trait WriteBytesExt: Write {
+        fn write_u8(&mut self, n: u8) -> Result<()> {
+            self.write_all(&[n])
+        }
+
+        fn write_u16<T: ByteOrder>(&mut self, n: u16) -> Result<()> {
+            let mut buf = [0; 2];
+            T::write_u16(&mut buf, n);
+            self.write_all(&buf)
+        }
+
+        fn write_u32<T: ByteOrder>(&mut self, n: u32) -> Result<()> {
+            let mut buf = [0; 4];
+            T::write_u32(&mut buf, n);
+            self.write_all(&buf)
+        }
+
+        fn write_i32<T: ByteOrder>(&mut self, n: i32) -> Result<()> {
+            let mut buf = [0; 4];
+            T::write_i32(&mut buf, n);
+            self.write_all(&buf)
+        }
+
+        fn write_f32<T: ByteOrder>(&mut self, n: f32) -> Result<()> {
+            let mut buf = [0; 4];
+            T::write_f32(&mut buf, n);
+            self.write_all(&buf)
+        }
+    }
+
+    impl<W: Write + ?Sized> WriteBytesExt for W {}
+}
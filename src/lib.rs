@@ -1,3 +1,4 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![warn(missing_docs)]
 #![warn(clippy::pedantic, clippy::perf, clippy::cargo)]
 #![allow(
@@ -41,25 +42,81 @@ tilemap.write(
 # Ok(())
 # }
 ```
+
+Without the default `std` feature, the crate is `no_std` and only needs `alloc`.
+Tilemaps can still be read from byte slices with [`TileMap::from_bytes`],
+and written with [`TileMap::to_bytes`] or [`TileMap::write_to_slice`].
+Anything taking a readable or writable goes through the traits of the re-exported `no_std_io2` instead.
  */
 
-use bytemuck::{cast_slice, Pod, Zeroable};
-use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
-use std::fmt::{Display, Formatter};
-use std::{
-    collections::HashMap,
-    io::{self, Cursor, Read, Write},
+extern crate alloc;
+
+use crate::io::{Read, ReadBytesExt, Seek, SeekFrom, Write, WriteBytesExt};
+use alloc::borrow::Cow;
+// Without std there's no `HashMap`, so maps are `BTreeMap`s, under the same name
+#[cfg(not(feature = "std"))]
+use alloc::collections::{btree_map::Entry, BTreeMap as HashMap};
+use bytemuck::{Pod, Zeroable};
+use byteorder::LittleEndian;
+use core::fmt::{Display, Formatter};
+use core::{
+    hash::{Hash, Hasher},
     iter,
-    ops::{Index, IndexMut},
+    ops::{Index, IndexMut, Range},
+};
+use prelude::*;
+#[cfg(feature = "std")]
+use std::collections::{hash_map::Entry, HashMap};
+#[cfg(feature = "std")]
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
 };
 
+#[cfg(all(feature = "libflate-backend", feature = "flate2-backend"))]
+compile_error!(
+    "the `libflate-backend` and `flate2-backend` features are mutually exclusive; \
+     disable the default features to use `flate2-backend`"
+);
+#[cfg(not(any(feature = "libflate-backend", feature = "flate2-backend")))]
+compile_error!("either the `libflate-backend` or the `flate2-backend` feature must be enabled");
+
+mod fnv;
 mod formatting;
+mod grid;
+mod io;
 mod read_helper;
+#[cfg(feature = "serde")]
+mod serde_impl;
 mod write_helper;
 
+#[cfg(feature = "encoding_rs")]
+pub use encoding_rs;
+/// The IO traits that tilemaps are read and written through without the `std` feature.
+#[cfg(not(feature = "std"))]
+pub use no_std_io2;
+
+/// The parts of the standard prelude that come from `alloc`, which `no_std` crates don't get.
+mod prelude {
+    pub(crate) use alloc::{borrow::ToOwned, boxed::Box, format, string::String, vec, vec::Vec};
+}
+
+/// The most sublayers a layer can store.
+/// The tiles and sublayers of a layer share a count byte.
+const MAX_SUBLAYERS: usize = 254;
+
 /// A representation of a tilemap file.
-#[derive(Clone, PartialEq, Default)]
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TileMap {
+    /// The file version of the tilemap.
+    /// This is set by [`TileMap::read`], and decides the layout [`TileMap::write`] emits,
+    /// so changing it changes what gets written.
+    pub version: u16,
+    /// The byte stored alongside the version, which is usually 1.
+    /// This is kept as-is, so that files which store something else are written back the same.
+    pub version_flags: u8,
     /// A collection of each layer of the tilemap.
     /// Any more than 65536 layers will not be saved.
     pub layers: Vec<Layer>,
@@ -68,23 +125,345 @@ pub struct TileMap {
     pub tilesets: Vec<TileSet>,
     /// The dynamic properties of the tilemap.
     /// Any more than 65536 properties will not be saved.
+    ///
+    /// Without the `std` feature, this is a `BTreeMap` instead, as are the crate's other maps.
     pub properties: HashMap<String, Property>,
+    /// Blocks that weren't recognized when reading, as their ID and their raw contents.
+    /// These are written back out verbatim after all other blocks.
+    ///
+    /// This includes the `ANIM` blocks some older tools emit,
+    /// as their layout isn't documented well enough to decode them reliably.
+    pub unknown_blocks: Vec<([u8; 4], Vec<u8>)>,
+    /// How the compressed data was framed in the file this was read from,
+    /// which is [`Framing::RawDeflate`] if any of the blocks were.
+    /// This decides how [`TileMap::write`] frames the data, so files are written back the same.
+    pub framing: Framing,
+    /// The original bytes of property keys and tileset paths that couldn't be decoded exactly,
+    /// keyed by the string they were decoded to.
+    ///
+    /// Any key or path equal to one of these strings is written as the original bytes,
+    /// so strings that aren't valid in their encoding are written back the same.
+    /// See [`TileMap::original_bytes`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub lossy_strings: HashMap<String, Vec<u8>>,
+    /// The tile that counts as empty in this tilemap. See [`TileMap::empty_tile`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    empty_tile: Tile,
+}
+
+impl Default for TileMap {
+    fn default() -> Self {
+        TileMap {
+            version: 5,
+            version_flags: 1,
+            layers: Vec::new(),
+            tilesets: Vec::new(),
+            properties: HashMap::new(),
+            unknown_blocks: Vec::new(),
+            framing: Framing::default(),
+            lossy_strings: HashMap::new(),
+            empty_tile: Tile::default(),
+        }
+    }
+}
+
+impl TryFrom<&[u8]> for TileMap {
+    type Error = ReadError;
+
+    /// Reads a tilemap from a byte slice, like [`TileMap::from_bytes`].
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        Self::from_bytes(bytes)
+    }
+}
+
+impl TryFrom<&TileMap> for Vec<u8> {
+    type Error = io::Error;
+
+    /// Writes a tilemap into a new buffer, like [`TileMap::to_bytes`].
+    fn try_from(tilemap: &TileMap) -> Result<Self, Self::Error> {
+        tilemap.to_bytes()
+    }
+}
+
+/// Options for reading a tilemap.
+#[derive(Debug, Clone)]
+// Each of these is an independent switch
+#[allow(clippy::struct_excessive_bools)]
+pub struct ReadOptions {
+    /// Whether to reject unknown blocks with [`ReadError::InvalidHeader`],
+    /// instead of keeping them in [`TileMap::unknown_blocks`].
+    ///
+    /// This also rejects any bytes after the last block that are too short to be a block,
+    /// which are ignored otherwise, as some exporters pad their files.
+    pub strict: bool,
+    /// Whether to check that each known block is exactly as long as its declared size,
+    /// raising [`ReadError::BlockSizeMismatch`] if not.
+    pub validate_block_sizes: bool,
+    /// The most bytes a single compressed block may decompress to,
+    /// raising [`ReadError::DecompressedTooLarge`] if it would be more.
+    ///
+    /// This guards against small files that decompress to huge amounts of data.
+    /// Defaults to 256 MiB.
+    pub max_decompressed_size: u64,
+    /// Whether to read integer properties holding `0` or `1` as [`Property::Boolean`]s.
+    pub coerce_bools: bool,
+    /// What to do with properties whose key already appeared earlier in the file.
+    pub on_duplicate_property: DuplicatePolicy,
+    /// Whether to [sanitize](Layer::sanitize) the opacity and scroll of each layer,
+    /// instead of keeping them exactly as they were stored.
+    pub clamp_floats: bool,
+    /// Whether to leave out the sublayers of each layer, leaving [`Layer::sublayers`] empty.
+    ///
+    /// Their data is still read past, but never decompressed,
+    /// which makes reading much faster when only the tiles are needed.
+    pub skip_sublayers: bool,
+    /// The encoding to decode property keys and tileset paths with,
+    /// or `None` to decode them as UTF-8.
+    ///
+    /// Either way, anything that can't be decoded is replaced with `U+FFFD`.
+    #[cfg(feature = "encoding_rs")]
+    pub encoding: Option<&'static encoding_rs::Encoding>,
+}
+
+/// What to do when a property key appears more than once in a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DuplicatePolicy {
+    /// Keep the value that appears last.
+    #[default]
+    KeepLast,
+    /// Keep the value that appears first.
+    KeepFirst,
+    /// Raise [`ReadError::DuplicateProperty`].
+    Error,
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        ReadOptions {
+            strict: false,
+            validate_block_sizes: false,
+            max_decompressed_size: 256 * 1024 * 1024,
+            coerce_bools: false,
+            on_duplicate_property: DuplicatePolicy::default(),
+            clamp_floats: false,
+            skip_sublayers: false,
+            #[cfg(feature = "encoding_rs")]
+            encoding: None,
+        }
+    }
+}
+
+/// How hard to compress the tile and sublayer data of a tilemap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CompressionLevel {
+    /// Store the data without compressing it.
+    /// This still emits a valid zlib stream.
+    None,
+    /// Compress quickly, at the cost of size.
+    Fast,
+    /// A balance between speed and size.
+    #[default]
+    Default,
+    /// Compress as small as possible, at the cost of speed.
+    Best,
+}
+
+/// Buffers for decompressing data into, which can be reused across reads
+/// with [`TileMap::read_with_scratch`] to save on allocations.
+///
+/// The buffers grow to fit the largest block read, and are never shrunk.
+#[derive(Debug, Clone, Default)]
+pub struct ReadScratch {
+    /// The compressed bytes of the last block.
+    pub(crate) encoded: Vec<u8>,
+    /// The decompressed bytes of the last block.
+    pub(crate) decoded: Vec<u8>,
+}
+
+impl ReadScratch {
+    /// Constructs a new instance with empty buffers.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// How the compressed tile and sublayer data of a tilemap is framed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Framing {
+    /// A zlib stream, which is what Clickteam writes.
+    #[default]
+    Zlib,
+    /// A raw deflate stream, without the zlib header and checksum,
+    /// which some third-party exporters write instead.
+    RawDeflate,
+}
+
+/// Options for writing a tilemap.
+#[derive(Debug, Clone)]
+pub struct WriteOptions {
+    /// The file version to write,
+    /// or `None` to use the tilemap's [`version`](TileMap::version).
+    pub version: Option<u16>,
+    /// How hard to compress the tile and sublayer data.
+    pub compression: CompressionLevel,
+    /// How to frame the compressed data,
+    /// or `None` to use the tilemap's [`framing`](TileMap::framing).
+    pub framing: Option<Framing>,
+    /// Whether to compress the tile and sublayer data on multiple threads.
+    /// This doesn't change the output. Defaults to `true`.
+    ///
+    /// Doing so compresses all of the data before writing any of it, which holds all of it in memory.
+    /// Otherwise, each block is compressed right before it's written.
+    #[cfg(feature = "rayon")]
+    pub parallel: bool,
+    /// Whether to check that every layer [is consistent](Layer::is_consistent),
+    /// raising [`WriteError::InconsistentLayer`] if not,
+    /// instead of writing a file that fails to be read.
+    pub strict: bool,
+    /// Whether to raise an error for property keys and tileset paths
+    /// longer than the 256 bytes a file can store, instead of truncating them.
+    pub strict_strings: bool,
+    /// The encoding to encode property keys and tileset paths with,
+    /// or `None` to encode them as UTF-8.
+    ///
+    /// Characters that the encoding can't represent are written as HTML numeric character references,
+    /// and lengths are counted after encoding.
+    #[cfg(feature = "encoding_rs")]
+    pub encoding: Option<&'static encoding_rs::Encoding>,
+}
+
+// Only derivable without the `rayon` feature
+#[allow(clippy::derivable_impls)]
+impl Default for WriteOptions {
+    fn default() -> Self {
+        WriteOptions {
+            version: None,
+            compression: CompressionLevel::default(),
+            framing: None,
+            #[cfg(feature = "rayon")]
+            parallel: true,
+            strict: false,
+            strict_strings: false,
+            #[cfg(feature = "encoding_rs")]
+            encoding: None,
+        }
+    }
 }
 
 /// A reason why reading a tilemap failed.
 pub enum ReadError {
     /// IO error.
     IoError(io::Error),
+    /// A compressed block failed to decompress, as its data was corrupt.
+    Decompression(io::Error),
     /// Invalid magic string.
     InvalidMagic,
     /// Unsupported version.
     UnsupportedVersion(u16),
     /// Invalid type in property map.
-    InvalidType(u8),
-    /// Layer length was not a multiple of two.
-    InvalidLayerLength,
+    InvalidType {
+        /// The invalid type.
+        ty: u8,
+        /// Byte offset of the type in the file.
+        offset: u64,
+    },
+    /// Layer length did not match its size.
+    ///
+    /// A layer's `MAIN` block has to hold exactly two bytes for each tile of its width and height,
+    /// and each of its `DATA` blocks exactly `cell_size` bytes for each tile.
+    /// A layer with an area has to have a `MAIN` block,
+    /// in which case the offset is that of its count of data blocks.
+    /// A layer whose area overflows a u32 is rejected too, with the offset of its size.
+    InvalidLayerLength {
+        /// Byte offset of the data block in the file.
+        offset: u64,
+    },
     /// Invalid header.
-    InvalidHeader(String),
+    InvalidHeader {
+        /// The invalid header, lossily converted to a string.
+        header: String,
+        /// Byte offset of the header in the file.
+        offset: u64,
+    },
+    /// A block's declared size did not match how many bytes it took up.
+    BlockSizeMismatch {
+        /// The ID of the block.
+        block: [u8; 4],
+        /// The declared size of the block.
+        expected: u32,
+        /// How many bytes the block actually took up.
+        actual: u64,
+        /// Byte offset of the block in the file.
+        offset: u64,
+    },
+    /// A compressed block decompressed to more than the limit.
+    DecompressedTooLarge {
+        /// The limit, from [`ReadOptions::max_decompressed_size`].
+        limit: u64,
+        /// Byte offset of the data block in the file.
+        offset: u64,
+    },
+    /// A block claimed to hold more items than there was room for in the rest of the stream.
+    ///
+    /// This is only checked when the length of the stream is known,
+    /// like when reading with [`TileMap::from_bytes`].
+    TruncatedBlock {
+        /// The ID of the block.
+        block: [u8; 4],
+        /// Byte offset of the block in the file.
+        offset: u64,
+    },
+    /// A property key appeared more than once,
+    /// and [`ReadOptions::on_duplicate_property`] was [`DuplicatePolicy::Error`].
+    DuplicateProperty(String),
+    /// The stream ended partway through the file.
+    ///
+    /// Running out of bytes between blocks is a clean end of the file, and isn't an error.
+    UnexpectedEof {
+        /// The ID of the block that was being read,
+        /// or `None` if the stream ended before the first block.
+        block: Option<[u8; 4]>,
+        /// Byte offset of the block in the file, or 0 if there was none.
+        offset: u64,
+    },
+}
+
+impl ReadError {
+    /// Returns the byte offset in the file where reading failed, if known.
+    #[must_use]
+    pub fn offset(&self) -> Option<u64> {
+        match self {
+            ReadError::IoError(_)
+            | ReadError::Decompression(_)
+            | ReadError::DuplicateProperty(_) => None,
+            ReadError::InvalidMagic => Some(0),
+            ReadError::UnsupportedVersion(_) => Some(8),
+            ReadError::InvalidType { offset, .. }
+            | ReadError::InvalidLayerLength { offset }
+            | ReadError::InvalidHeader { offset, .. }
+            | ReadError::BlockSizeMismatch { offset, .. }
+            | ReadError::TruncatedBlock { offset, .. }
+            | ReadError::DecompressedTooLarge { offset, .. }
+            | ReadError::UnexpectedEof { offset, .. } => Some(*offset),
+        }
+    }
+
+    /// Turn an IO error from running out of bytes into [`ReadError::UnexpectedEof`],
+    /// saying which block was being read.
+    fn in_block(self, block: Option<([u8; 4], u64)>) -> Self {
+        match self {
+            ReadError::IoError(err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                ReadError::UnexpectedEof {
+                    block: block.map(|(id, _)| id),
+                    offset: block.map_or(0, |(_, offset)| offset),
+                }
+            }
+            err => err,
+        }
+    }
 }
 impl From<io::Error> for ReadError {
     fn from(err: io::Error) -> Self {
@@ -92,570 +471,3684 @@ impl From<io::Error> for ReadError {
     }
 }
 
-impl std::fmt::Debug for ReadError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Debug for ReadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         match self {
             ReadError::IoError(err) => write!(f, "{err}"),
+            ReadError::Decompression(err) => write!(f, "failed to decompress data block: {err}"),
             ReadError::UnsupportedVersion(v) => {
                 write!(f, "version {v} of tilemap files is not supported")
             }
-            ReadError::InvalidType(ty) => {
-                write!(f, "found invalid type 0x{ty:02X} in property mapping")
+            ReadError::InvalidType { ty, offset } => write!(
+                f,
+                "found invalid type 0x{ty:02X} in property mapping at offset {offset}"
+            ),
+            ReadError::InvalidHeader { header, offset } => {
+                write!(f, "found invalid header \"{header}\" at offset {offset}")
             }
-            ReadError::InvalidHeader(head) => write!(f, "found invalid header \"{head}\""),
             ReadError::InvalidMagic => write!(f, "found invalid magic string for tilemap"),
-            ReadError::InvalidLayerLength => write!(f, "layer byte length did not match its size"),
+            ReadError::InvalidLayerLength { offset } => write!(
+                f,
+                "layer byte length did not match its size at offset {offset}"
+            ),
+            ReadError::BlockSizeMismatch {
+                block,
+                expected,
+                actual,
+                offset,
+            } => write!(
+                f,
+                "block \"{}\" at offset {offset} declared a size of {expected} bytes, but took up {actual}",
+                String::from_utf8_lossy(block)
+            ),
+            ReadError::DecompressedTooLarge { limit, offset } => write!(
+                f,
+                "data block at offset {offset} decompressed to more than the limit of {limit} bytes"
+            ),
+            ReadError::TruncatedBlock { block, offset } => write!(
+                f,
+                "block \"{}\" at offset {offset} claimed more items than the rest of the file can hold",
+                String::from_utf8_lossy(block)
+            ),
+            ReadError::DuplicateProperty(key) => {
+                write!(f, "found property key {key:?} more than once")
+            }
+            ReadError::UnexpectedEof {
+                block: Some(block),
+                offset,
+            } => write!(
+                f,
+                "file ended partway through block \"{}\" at offset {offset}",
+                String::from_utf8_lossy(block)
+            ),
+            ReadError::UnexpectedEof { block: None, .. } => {
+                write!(f, "file ended before its first block")
+            }
         }
     }
 }
 
 impl Display for ReadError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f, "{self:?}")
     }
 }
 
-impl std::error::Error for ReadError {}
+impl core::error::Error for ReadError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            ReadError::IoError(err) | ReadError::Decompression(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// A reason why writing a tilemap failed.
+pub enum WriteError {
+    /// IO error.
+    IoError(io::Error),
+    /// Unsupported version.
+    UnsupportedVersion(u16),
+    /// The tilemap holds data that the given version cannot store, described by the string.
+    Unrepresentable(u16, &'static str),
+    /// The layer at this index isn't [consistent](Layer::is_consistent).
+    InconsistentLayer(usize),
+    /// This property key is longer than 256 bytes,
+    /// and [`strict_strings`](WriteOptions::strict_strings) was set.
+    PropertyKeyTooLong(String),
+    /// The path of the tileset at this index is longer than 256 bytes,
+    /// and [`strict_strings`](WriteOptions::strict_strings) was set.
+    TilesetPathTooLong(usize),
+    /// There were more of something than the file format can store,
+    /// raised by [`TileMap::write_checked`].
+    LimitExceeded {
+        /// What there were too many of.
+        kind: LimitKind,
+        /// How many there were.
+        count: usize,
+        /// How many can be stored.
+        max: usize,
+    },
+    /// There is no layer at this index to write,
+    /// raised by [`TileMap::write_layers`].
+    NoSuchLayer(usize),
+}
 
-/// A helper struct to make writing headers easier.
-struct Header<'a, 'b, W: Write> {
-    stream: &'a mut W,
-    buffer: Cursor<Vec<u8>>,
-    header: &'b [u8],
+/// Something in a tilemap that the file format can only store so many of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LimitKind {
+    /// Layers of the tilemap.
+    Layers,
+    /// Tilesets of the tilemap.
+    Tilesets,
+    /// Properties of the tilemap.
+    Properties,
+    /// Sublayers of the layer at this index.
+    Sublayers(usize),
 }
 
-impl<'a, 'b, W: Write> Header<'a, 'b, W> {
-    #[must_use = "header won't write if dropped"]
-    fn new(stream: &'a mut W, header: &'b [u8]) -> Self {
-        Header {
-            stream,
-            buffer: Cursor::new(Vec::new()),
-            header,
-        }
+impl From<io::Error> for WriteError {
+    fn from(err: io::Error) -> Self {
+        WriteError::IoError(err)
     }
+}
 
-    fn write_header(self) -> io::Result<()> {
-        self.stream.write_all(self.header)?;
-        self.stream
-            .write_all(&(self.buffer.get_ref().len() as u32).to_le_bytes())?;
-        self.stream.write_all(self.buffer.get_ref())
+impl From<WriteError> for io::Error {
+    fn from(err: WriteError) -> Self {
+        match err {
+            WriteError::IoError(err) => err,
+            err => io::Error::new(io::ErrorKind::InvalidData, err),
+        }
     }
 }
 
-impl<'a, 'b, W: Write> Write for Header<'a, 'b, W> {
-    fn write(&mut self, buf: &[u8]) -> Result<usize, io::Error> {
-        self.buffer.write(buf)
+impl core::fmt::Debug for WriteError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            WriteError::IoError(err) => write!(f, "{err}"),
+            WriteError::UnsupportedVersion(v) => {
+                write!(f, "version {v} of tilemap files is not supported")
+            }
+            WriteError::Unrepresentable(v, data) => {
+                write!(f, "version {v} of tilemap files cannot store {data}")
+            }
+            WriteError::InconsistentLayer(index) => write!(
+                f,
+                "layer {index} holds a different amount of data than its size says"
+            ),
+            WriteError::PropertyKeyTooLong(key) => write!(
+                f,
+                "property key {key:?} is {} bytes long, but at most 256 can be stored",
+                key.len()
+            ),
+            WriteError::LimitExceeded { kind, count, max } => {
+                let what = match kind {
+                    LimitKind::Layers => String::from("layers"),
+                    LimitKind::Tilesets => String::from("tilesets"),
+                    LimitKind::Properties => String::from("properties"),
+                    LimitKind::Sublayers(index) => format!("sublayers in layer {index}"),
+                };
+                write!(
+                    f,
+                    "there are {count} {what}, but at most {max} can be stored"
+                )
+            }
+            WriteError::TilesetPathTooLong(index) => write!(
+                f,
+                "the path of tileset {index} is longer than the 256 bytes that can be stored"
+            ),
+            WriteError::NoSuchLayer(index) => write!(f, "there is no layer {index} to write"),
+        }
     }
+}
 
-    fn flush(&mut self) -> Result<(), io::Error> {
-        Ok(())
+impl Display for WriteError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{self:?}")
     }
 }
 
-impl TileMap {
-    /// Attempt to read a tilemap from a readable.
-    ///
-    /// # Errors
-    /// Errors if the file fails to be read.
-    pub fn read(mut cursor: impl Read) -> Result<Self, ReadError> {
-        // Read the magic string, see if it matches
-        let mut buf = [0; 8];
-        cursor.read_exact(&mut buf)?;
-        if &buf != b"ACHTUNG!" {
-            return Err(ReadError::InvalidMagic);
+impl core::error::Error for WriteError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            WriteError::IoError(err) => Some(err),
+            _ => None,
         }
-        // There's an extra bit flipped on for whatever reason
-        // We get rid of it here
-        let version = cursor.read_u16::<LittleEndian>()? ^ 0b1_0000_0000;
-        if version > 5 {
-            return Err(ReadError::UnsupportedVersion(version));
+    }
+}
+
+/// A position was outside of a layer or sublayer.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct OutOfBounds {
+    /// The X position.
+    pub x: u32,
+    /// The Y position.
+    pub y: u32,
+    /// Width of the layer or sublayer.
+    pub width: u32,
+    /// Height of the layer or sublayer.
+    pub height: u32,
+}
+
+impl core::fmt::Debug for OutOfBounds {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "position ({}, {}) is out of bounds of a {}x{} grid",
+            self.x, self.y, self.width, self.height
+        )
+    }
+}
+
+impl Display for OutOfBounds {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl core::error::Error for OutOfBounds {}
+
+/// A sublayer default value was longer than the 4 bytes a cell can hold.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct DefaultTooLong {
+    /// The length of the default value.
+    pub len: usize,
+}
+
+impl core::fmt::Debug for DefaultTooLong {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "default value is {} bytes long, but cells can be at most 4 bytes",
+            self.len
+        )
+    }
+}
+
+impl Display for DefaultTooLong {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl core::error::Error for DefaultTooLong {}
+
+/// The area of a new layer or sublayer size didn't fit in a u32.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct AreaOverflow {
+    /// The width that was asked for.
+    pub width: u32,
+    /// The height that was asked for.
+    pub height: u32,
+}
+
+impl AreaOverflow {
+    /// Check that the area of a size fits in a u32.
+    fn check(width: u32, height: u32) -> Result<(), Self> {
+        match width.checked_mul(height) {
+            Some(_) => Ok(()),
+            None => Err(AreaOverflow { width, height }),
         }
-        let mut tilemap = TileMap::default();
-        let mut global_dimensions = (16, 16);
-        loop {
-            let mut block_id = [0; 4];
-            if let Err(err) = cursor.read_exact(&mut block_id) {
-                if matches!(err.kind(), io::ErrorKind::UnexpectedEof) {
-                    // Reached EOF, stop
-                    break;
-                }
-                // Other IO error, raise it
-                return Err(ReadError::IoError(err));
-            }
-            // Block size is of no use to us
-            let _block_size = cursor.read_u32::<LittleEndian>()?;
-            match &block_id {
-                b"MAP " => {
-                    // Mapping of strings to arbitrary data
-                    if version >= 3 {
-                        let count = cursor.read_u16::<LittleEndian>()?;
-                        for _ in 0..count {
-                            let name = read_helper::read_short_string(&mut cursor)?;
-                            let ty = cursor.read_u8()?;
-                            let property = match ty {
-                                // Integer
-                                0 => Property::Integer(cursor.read_i32::<LittleEndian>()?),
-                                1 => Property::Float(cursor.read_f32::<LittleEndian>()?),
-                                2 => Property::String(read_helper::read_long_string(&mut cursor)?),
-                                t => return Err(ReadError::InvalidType(t)),
-                            };
-                            let _ = tilemap
-                                .properties
-                                .insert(String::from_utf8_lossy(&name).into_owned(), property);
-                        }
-                    } else {
-                        // Deprecated, only in older versions
-                        global_dimensions = (
-                            cursor.read_u16::<LittleEndian>()?,
-                            cursor.read_u16::<LittleEndian>()?,
-                        );
-                    }
-                }
-                b"TILE" => {
-                    let amount = cursor.read_u8()?;
-                    for _ in 0..amount {
-                        // Color is stored in xBGR
-                        let mut buf = [0; 4];
-                        cursor.read_exact(&mut buf)?;
-                        let raw_path = read_helper::read_short_string(&mut cursor)?;
-                        tilemap.tilesets.push(TileSet {
-                            path: String::from_utf8_lossy(&raw_path).into_owned(),
-                            transparent_color: (buf[3], buf[2], buf[1]),
-                        });
-                    }
-                }
-                b"LAYR" => {
-                    let amount = if version == 0 {
-                        cursor.read_u8()? as u16
-                    } else {
-                        cursor.read_u16::<LittleEndian>()?
-                    };
-                    for _ in 0..amount {
-                        let mut layer = Layer::default();
-                        let (width, height) = (
-                            cursor.read_u32::<LittleEndian>()?,
-                            cursor.read_u32::<LittleEndian>()?,
-                        );
-                        layer.width = width;
-                        layer.height = height;
-                        layer.tile_dimensions = if version >= 2 {
-                            (
-                                cursor.read_u16::<LittleEndian>()?,
-                                cursor.read_u16::<LittleEndian>()?,
-                            )
-                        } else {
-                            // Read global dimensions
-                            global_dimensions
-                        };
-                        // Python struct syntax: =2B2i2f3?f
-                        (
-                            layer.tileset,
-                            layer.collision,
-                            layer.offset,
-                            layer.scroll,
-                            layer.wrap,
-                            layer.visible,
-                            layer.opacity,
-                        ) = (
-                            cursor.read_u8()?,
-                            cursor.read_u8()?,
-                            (
-                                cursor.read_i32::<LittleEndian>()?,
-                                cursor.read_i32::<LittleEndian>()?,
-                            ),
-                            (
-                                cursor.read_f32::<LittleEndian>()?,
-                                cursor.read_f32::<LittleEndian>()?,
-                            ),
-                            (cursor.read_u8()? > 0, cursor.read_u8()? > 0),
-                            cursor.read_u8()? > 0,
-                            cursor.read_f32::<LittleEndian>()?,
-                        );
-                        // Read sublayer link
-                        if version >= 4 {
-                            layer.sublayer_link.tileset = cursor.read_u8()?;
-                            layer.sublayer_link.animation = cursor.read_u8()?;
-                            if version == 5 {
-                                layer.sublayer_link.animation_frame = cursor.read_u8()?;
-                            }
-                        }
-                        // Read data blocks
-                        let data_count = cursor.read_u8()?;
-                        let mut header_buf = [0; 4];
-                        for _ in 0..data_count {
-                            cursor.read_exact(&mut header_buf)?;
-                            match &header_buf {
-                                b"MAIN" => {
-                                    // Read the tiles
-                                    let raw_tiles = read_helper::read_compressed(&mut cursor)?;
-                                    if raw_tiles.len() % 2 != 0 {
-                                        return Err(ReadError::InvalidLayerLength);
-                                    }
-                                    // We cannot do reinterpretation here,
-                                    // since Tile.id has an alignment of 2,
-                                    // while the vector has an alignment of 1.
-                                    layer.data = raw_tiles
-                                        .into_boxed_slice()
-                                        .chunks(2)
-                                        .map(|chunk| Tile {
-                                            position: if cfg!(target_endian = "big") {
-                                                [chunk[0], chunk[1]]
-                                            } else {
-                                                [chunk[1], chunk[0]]
-                                            },
-                                        })
-                                        .collect();
-                                }
-                                b"DATA" => {
-                                    let cell_size = cursor.read_u8()?.min(4);
-                                    let mut default_value = [0; 4];
-                                    cursor.read_exact(&mut default_value)?;
-                                    let (w, h) = (layer.width, layer.height);
-                                    let sublayer =
-                                        layer.add_sublayer(&default_value[..cell_size as usize]);
-                                    sublayer.resize(w, h);
-                                    let sublayer_data = read_helper::read_compressed(&mut cursor)?;
-                                    if sublayer_data.len()
-                                        != (sublayer.width as usize
-                                            * sublayer.height as usize
-                                            * sublayer.cell_size as usize)
-                                    {
-                                        return Err(ReadError::InvalidLayerLength);
-                                    }
-                                    sublayer.data = sublayer_data;
-                                }
-                                header => {
-                                    let header = String::from_utf8_lossy(header).into_owned();
-                                    return Err(ReadError::InvalidHeader(header));
-                                }
-                            }
-                        }
-                        tilemap.layers.push(layer);
-                    }
-                }
-                header => {
-                    let header = String::from_utf8_lossy(header).into_owned();
-                    return Err(ReadError::InvalidHeader(header));
-                }
-            }
+    }
+}
+
+impl core::fmt::Debug for AreaOverflow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "area of {}x{} overflows a u32", self.width, self.height)
+    }
+}
+
+impl Display for AreaOverflow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl core::error::Error for AreaOverflow {}
+
+/// Appending a tilemap would have left more tilesets than a file can store,
+/// raised by [`TileMap::append`].
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct TooManyTilesets {
+    /// How many tilesets there would have been.
+    pub count: usize,
+}
+
+impl core::fmt::Debug for TooManyTilesets {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "there would be {} tilesets, but at most {} can be stored",
+            self.count,
+            u8::MAX
+        )
+    }
+}
+
+impl Display for TooManyTilesets {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl core::error::Error for TooManyTilesets {}
+
+/// A reason why setting a cell of a sublayer failed.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SubLayerSetError {
+    /// The position was out of bounds.
+    OutOfBounds(OutOfBounds),
+    /// The cell wasn't the same length as the sublayer's cell size.
+    WrongLength {
+        /// The cell size of the sublayer.
+        expected: u8,
+        /// The length of the given cell.
+        actual: usize,
+    },
+}
+
+impl From<OutOfBounds> for SubLayerSetError {
+    fn from(err: OutOfBounds) -> Self {
+        SubLayerSetError::OutOfBounds(err)
+    }
+}
+
+impl core::fmt::Debug for SubLayerSetError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SubLayerSetError::OutOfBounds(err) => write!(f, "{err:?}"),
+            SubLayerSetError::WrongLength { expected, actual } => write!(
+                f,
+                "cell was {actual} bytes long, but the sublayer's cells are {expected} bytes long"
+            ),
         }
-        Ok(tilemap)
     }
+}
 
-    /// Attempts to write a tilemap to a writable.
-    ///
-    /// # Errors
-    /// The file failed to be written.
-    pub fn write(&self, mut cursor: impl Write) -> Result<(), io::Error> {
-        // Write magic string
-        cursor.write_all(b"ACHTUNG!")?;
-        // Always write version 5
-        // The version has an extra bit
-        cursor.write_u8(5)?;
-        cursor.write_u8(1)?;
-        if !self.properties.is_empty() {
-            let mut cur = Header::new(&mut cursor, b"MAP ");
-            // Can only store up to 65535 properties
-            cur.write_u16::<LittleEndian>(self.properties.len().min(u16::MAX as usize) as u16)?;
-            for (key, value) in self.properties.iter().take(0xFFFF) {
-                write_helper::write_short_string(&mut cur, key)?;
-                match value {
-                    Property::Integer(i) => {
-                        cur.write_u8(0)?; // Integer: 0
-                        cur.write_i32::<LittleEndian>(*i)?;
-                    }
-                    Property::Float(f) => {
-                        cur.write_u8(1)?; // Float: 1
-                        cur.write_f32::<LittleEndian>(*f)?;
-                    }
-                    Property::String(s) => {
-                        cur.write_u8(2)?; // String: 2
-                        write_helper::write_long_string(&mut cur, s)?;
-                    }
+impl Display for SubLayerSetError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl core::error::Error for SubLayerSetError {}
+
+/// A reason why adding a sublayer with existing data failed.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SubLayerShapeError {
+    /// The default value was longer than 4 bytes.
+    DefaultTooLong(DefaultTooLong),
+    /// The data wasn't as long as the layer's area times the cell size.
+    WrongLength {
+        /// How long the data should have been.
+        expected: usize,
+        /// How long the data was.
+        actual: usize,
+    },
+}
+
+impl From<DefaultTooLong> for SubLayerShapeError {
+    fn from(err: DefaultTooLong) -> Self {
+        SubLayerShapeError::DefaultTooLong(err)
+    }
+}
+
+impl core::fmt::Debug for SubLayerShapeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SubLayerShapeError::DefaultTooLong(err) => write!(f, "{err:?}"),
+            SubLayerShapeError::WrongLength { expected, actual } => write!(
+                f,
+                "sublayer data was {actual} bytes long, but should have been {expected} bytes long"
+            ),
+        }
+    }
+}
+
+impl Display for SubLayerShapeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl core::error::Error for SubLayerShapeError {}
+
+/// A reason why pasting tiles into a layer failed.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum PasteError {
+    /// The region didn't fit within the layer.
+    OutOfBounds {
+        /// The region, as its X and Y position followed by its width and height.
+        region: (u32, u32, u32, u32),
+        /// Width of the layer.
+        width: u32,
+        /// Height of the layer.
+        height: u32,
+    },
+    /// There weren't as many tiles as the region's area.
+    WrongLength {
+        /// How many tiles there should have been.
+        expected: usize,
+        /// How many tiles there were.
+        actual: usize,
+    },
+}
+
+impl core::fmt::Debug for PasteError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            PasteError::OutOfBounds {
+                region,
+                width,
+                height,
+            } => write!(
+                f,
+                "{}x{} region at ({}, {}) is out of bounds of a {width}x{height} layer",
+                region.2, region.3, region.0, region.1
+            ),
+            PasteError::WrongLength { expected, actual } => write!(
+                f,
+                "{actual} tiles were given, but the region holds {expected} tiles"
+            ),
+        }
+    }
+}
+
+impl Display for PasteError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl core::error::Error for PasteError {}
+
+/// The rows of a grid had differing lengths.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct RaggedGrid {
+    /// Index of the first row that differed in length from the first row.
+    pub row: usize,
+    /// The length of the first row.
+    pub expected: usize,
+    /// The length of the differing row.
+    pub actual: usize,
+}
+
+impl core::fmt::Debug for RaggedGrid {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "row {} was {} long, but the first row was {} long",
+            self.row, self.actual, self.expected
+        )
+    }
+}
+
+impl Display for RaggedGrid {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl core::error::Error for RaggedGrid {}
+
+/// A reason why building a layer from its tiles failed.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum LayerShapeError {
+    /// The area of the layer didn't fit in a u32.
+    AreaOverflow(AreaOverflow),
+    /// There weren't as many tiles as the area of the layer.
+    WrongLength {
+        /// How many tiles there should have been.
+        expected: usize,
+        /// How many tiles there were, counting at most one past `expected`.
+        actual: usize,
+    },
+}
+
+impl From<AreaOverflow> for LayerShapeError {
+    fn from(err: AreaOverflow) -> Self {
+        LayerShapeError::AreaOverflow(err)
+    }
+}
+
+impl core::fmt::Debug for LayerShapeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            LayerShapeError::AreaOverflow(err) => write!(f, "{err:?}"),
+            LayerShapeError::WrongLength { expected, actual } if actual > expected => {
+                write!(f, "more than {expected} tiles were given")
+            }
+            LayerShapeError::WrongLength { expected, actual } => write!(
+                f,
+                "{actual} tiles were given, but the layer holds {expected} tiles"
+            ),
+        }
+    }
+}
+
+impl Display for LayerShapeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl core::error::Error for LayerShapeError {}
+
+/// A reason why parsing a hex color failed.
+#[derive(Clone, PartialEq, Eq)]
+pub enum ParseColorError {
+    /// The color wasn't 6 digits long, optionally after a `#`. Holds the amount of digits.
+    InvalidLength(usize),
+    /// The color held a character that isn't a hex digit.
+    InvalidDigit(char),
+}
+
+impl core::fmt::Debug for ParseColorError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ParseColorError::InvalidLength(len) => {
+                write!(f, "expected 6 hex digits in color, found {len}")
+            }
+            ParseColorError::InvalidDigit(c) => write!(f, "invalid hex digit {c:?} in color"),
+        }
+    }
+}
+
+impl Display for ParseColorError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl core::error::Error for ParseColorError {}
+
+/// Something in a tilemap that would be lost or rejected when writing it,
+/// as found by [`TileMap::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationWarning {
+    /// There are more layers than the file version can store. Holds the amount of layers.
+    TooManyLayers(usize),
+    /// There are more than 255 tilesets. Holds the amount of tilesets.
+    TooManyTilesets(usize),
+    /// There are more than 65535 properties. Holds the amount of properties.
+    TooManyProperties(usize),
+    /// A property has an empty key, which fails to write.
+    EmptyPropertyKey,
+    /// A property key is longer than 256 bytes, and will be truncated. Holds the key.
+    PropertyKeyTooLong(String),
+    /// A string property is empty, which fails to write. Holds the key of the property.
+    EmptyStringProperty(String),
+    /// A layer has more than 254 sublayers.
+    SublayerCountExceeded {
+        /// Index of the layer.
+        layer: usize,
+        /// The amount of sublayers in the layer.
+        count: usize,
+    },
+    /// A tileset has an empty path, which fails to write. Holds the index of the tileset.
+    EmptyTilesetPath(usize),
+    /// A tileset path is longer than 256 bytes, and will be truncated.
+    /// Holds the index of the tileset.
+    TilesetPathTooLong(usize),
+}
+
+impl Display for ValidationWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ValidationWarning::TooManyLayers(count) => {
+                write!(f, "{count} layers is more than the file can store")
+            }
+            ValidationWarning::TooManyTilesets(count) => {
+                write!(f, "{count} tilesets is more than the maximum of 255")
+            }
+            ValidationWarning::TooManyProperties(count) => {
+                write!(f, "{count} properties is more than the maximum of 65535")
+            }
+            ValidationWarning::EmptyPropertyKey => write!(f, "a property has an empty key"),
+            ValidationWarning::PropertyKeyTooLong(key) => {
+                write!(f, "property key {key:?} is longer than 256 bytes")
+            }
+            ValidationWarning::EmptyStringProperty(key) => {
+                write!(f, "string property {key:?} is empty")
+            }
+            ValidationWarning::SublayerCountExceeded { layer, count } => write!(
+                f,
+                "layer {layer} has {count} sublayers, more than the maximum of 254"
+            ),
+            ValidationWarning::EmptyTilesetPath(index) => {
+                write!(f, "tileset {index} has an empty path")
+            }
+            ValidationWarning::TilesetPathTooLong(index) => {
+                write!(f, "the path of tileset {index} is longer than 256 bytes")
+            }
+        }
+    }
+}
+
+/// A helper struct to make writing headers easier.
+///
+/// If the stream can seek, the block is written straight to it,
+/// and its length is filled in once it's done.
+/// Otherwise, the block is buffered until then.
+struct Header<'a, 'b, W: write_helper::Sink> {
+    stream: &'a mut W,
+    body: BlockBody,
+    id: &'b [u8],
+}
+
+/// Where the body of a block goes while it's being written.
+enum BlockBody {
+    /// Buffered, to be written with its length at the end.
+    Buffered(Vec<u8>),
+    /// Written straight to the stream, with its length to be filled in at this position.
+    Streamed { length_at: u64, length: u64 },
+}
+
+impl<'a, 'b, W: write_helper::Sink> Header<'a, 'b, W> {
+    #[must_use = "header won't write if dropped"]
+    fn new(stream: &'a mut W, id: &'b [u8]) -> io::Result<Self> {
+        let body = match stream.position()? {
+            Some(start) => {
+                stream.write_all(id)?;
+                // Filled in by write_header
+                stream.write_all(&[0; 4])?;
+                BlockBody::Streamed {
+                    length_at: start + id.len() as u64,
+                    length: 0,
                 }
             }
-            cur.write_header()?;
+            None => BlockBody::Buffered(Vec::new()),
+        };
+        Ok(Header { stream, body, id })
+    }
+
+    fn write_header(self) -> io::Result<()> {
+        match self.body {
+            BlockBody::Buffered(buffer) => {
+                self.stream.write_all(self.id)?;
+                self.stream
+                    .write_all(&(buffer.len() as u32).to_le_bytes())?;
+                self.stream.write_all(&buffer)
+            }
+            BlockBody::Streamed { length_at, length } => {
+                self.stream.patch_length(length_at, length as u32)
+            }
+        }
+    }
+}
+
+impl<W: write_helper::Sink> Write for Header<'_, '_, W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, io::Error> {
+        match &mut self.body {
+            BlockBody::Buffered(buffer) => buffer.write(buf),
+            BlockBody::Streamed { length, .. } => {
+                let written = self.stream.write(buf)?;
+                *length += written as u64;
+                Ok(written)
+            }
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), io::Error> {
+        Ok(())
+    }
+}
+
+impl TileMap {
+    /// Attempt to read a tilemap from a readable.
+    ///
+    /// Unknown blocks are kept in [`TileMap::unknown_blocks`].
+    ///
+    /// # Errors
+    /// Errors if the file fails to be read.
+    pub fn read(cursor: impl Read) -> Result<Self, ReadError> {
+        Self::read_with_options(cursor, &ReadOptions::default())
+    }
+
+    /// Attempt to read a tilemap from a readable, with the given options.
+    ///
+    /// # Errors
+    /// Errors if the file fails to be read.
+    pub fn read_with_options(cursor: impl Read, options: &ReadOptions) -> Result<Self, ReadError> {
+        Self::read_with_progress(cursor, options, |_, _, _| {}, |_, _| {})
+    }
+
+    /// Attempt to read a tilemap from a readable, with the given options,
+    /// reporting progress along the way.
+    ///
+    /// `on_block` is called with the ID, declared length and stream position of each block
+    /// as its header is read, and `on_layer` is called with the index of each layer
+    /// and the number of layers in its block once that layer has been read.
+    /// Neither changes what gets read.
+    ///
+    /// # Errors
+    /// Errors if the file fails to be read.
+    pub fn read_with_progress(
+        cursor: impl Read,
+        options: &ReadOptions,
+        on_block: impl FnMut([u8; 4], u32, u64),
+        on_layer: impl FnMut(usize, usize),
+    ) -> Result<Self, ReadError> {
+        let mut scratch = ReadScratch::new();
+        // Keep track of where we are, for error reporting
+        let mut cursor = read_helper::Counter::new(cursor);
+        Self::read_into_scratch(
+            &mut cursor,
+            options,
+            on_block,
+            on_layer,
+            |_, _, _| {},
+            &mut scratch,
+        )
+    }
+
+    /// Attempt to read a tilemap from a readable,
+    /// decompressing its data through buffers that are kept for the next read.
+    ///
+    /// Reusing one [`ReadScratch`] across many reads saves reallocating those buffers each time.
+    ///
+    /// # Errors
+    /// Errors if the file fails to be read.
+    pub fn read_with_scratch(
+        cursor: impl Read,
+        scratch: &mut ReadScratch,
+    ) -> Result<Self, ReadError> {
+        let options = ReadOptions::default();
+        let mut cursor = read_helper::Counter::new(cursor);
+        Self::read_into_scratch(
+            &mut cursor,
+            &options,
+            |_, _, _| {},
+            |_, _| {},
+            |_, _, _| {},
+            scratch,
+        )
+    }
+
+    /// Attempt to read a tilemap from a readable, with the given options,
+    /// also returning the compressed bytes of each data block of each layer.
+    ///
+    /// The bytes are keyed by the index of their layer and which block of it they are,
+    /// and don't include the length that comes before them in the file.
+    /// The tilemap is the same as the one [`TileMap::read_with_options`] returns.
+    ///
+    /// # Errors
+    /// Errors if the file fails to be read.
+    pub fn read_with_raw_blocks(
+        cursor: impl Read,
+        options: &ReadOptions,
+    ) -> Result<(Self, RawBlocks), ReadError> {
+        let mut raw_blocks = HashMap::new();
+        let mut cursor = read_helper::Counter::new(cursor);
+        let tilemap = Self::read_into_scratch(
+            &mut cursor,
+            options,
+            |_, _, _| {},
+            |_, _| {},
+            |layer, kind, bytes| {
+                raw_blocks.insert((layer, kind), bytes.to_vec());
+            },
+            &mut ReadScratch::new(),
+        )?;
+        Ok((tilemap, raw_blocks))
+    }
+
+    /// Read a tilemap, reporting progress and decompressing through the scratch buffers.
+    ///
+    /// `on_raw_block` is called with the index of each layer,
+    /// and the kind and compressed bytes of each of its data blocks.
+    fn read_into_scratch<R: Read>(
+        cursor: &mut read_helper::Counter<R>,
+        options: &ReadOptions,
+        on_block: impl FnMut([u8; 4], u32, u64),
+        mut on_layer: impl FnMut(usize, usize),
+        mut on_raw_block: impl FnMut(usize, BlockKind, &[u8]),
+        scratch: &mut ReadScratch,
+    ) -> Result<Self, ReadError> {
+        let mut layers = Vec::new();
+        let mut framing = Framing::Zlib;
+        let mut tilemap = Self::read_blocks(
+            cursor,
+            options,
+            on_block,
+            |cursor, header, (index, total)| {
+                if index == 0 {
+                    // This is the first layer of its block, so the rest of the block is still to come
+                    layers.reserve(total);
+                }
+                let mut layer = header.to_layer();
+                let index = layers.len();
+                let on_raw_block = |kind, bytes: &[u8]| on_raw_block(index, kind, bytes);
+                if read_layer_data(cursor, &mut layer, options, scratch, on_raw_block)?
+                    == Framing::RawDeflate
+                {
+                    framing = Framing::RawDeflate;
+                }
+                layers.push(layer);
+                on_layer(layers.len() - 1, total);
+                Ok(())
+            },
+        )?;
+        tilemap.layers = layers;
+        tilemap.framing = framing;
+        Ok(tilemap)
+    }
+
+    /// Attempt to read a tilemap from a byte slice.
+    ///
+    /// As the length is known, blocks claiming to hold more items than the bytes have room for
+    /// raise [`ReadError::TruncatedBlock`] before any of them are read.
+    ///
+    /// # Errors
+    /// Errors if the file fails to be read.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ReadError> {
+        Self::from_bytes_consumed(bytes).map(|(tilemap, _)| tilemap)
+    }
+
+    /// Attempt to read a tilemap from a byte slice, like [`TileMap::from_bytes`],
+    /// also returning how many bytes were consumed.
+    ///
+    /// The format has no end marker, so blocks are read until the bytes run out.
+    /// The count is where the last block ended, which leaves out any trailing padding
+    /// too short to be a block.
+    ///
+    /// # Errors
+    /// Errors if the file fails to be read.
+    pub fn from_bytes_consumed(bytes: &[u8]) -> Result<(Self, usize), ReadError> {
+        let mut cursor = read_helper::Counter::new(bytes);
+        // Knowing where the bytes end lets blocks that claim too much be rejected up front
+        cursor.end = Some(bytes.len() as u64);
+        let options = ReadOptions::default();
+        let mut scratch = ReadScratch::new();
+        let tilemap = Self::read_into_scratch(
+            &mut cursor,
+            &options,
+            |_, _, _| {},
+            |_, _| {},
+            |_, _, _| {},
+            &mut scratch,
+        )?;
+        // The position can't be past the end of the bytes
+        Ok((tilemap, cursor.position as usize))
+    }
+
+    /// Attempts to write a tilemap to a new byte vector.
+    ///
+    /// # Errors
+    /// The tilemap holds data that its version can't store.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, io::Error> {
+        let mut bytes = Vec::new();
+        self.write_into(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Attempts to write a tilemap to the end of a byte vector,
+    /// reserving room for it up front.
+    ///
+    /// The room reserved is what the tilemap would take up uncompressed,
+    /// so this never reallocates more than once unless the data doesn't compress at all.
+    ///
+    /// # Errors
+    /// The tilemap holds data that its version can't store.
+    pub fn write_into(&self, buf: &mut Vec<u8>) -> Result<(), io::Error> {
+        buf.reserve(self.estimated_size());
+        self.write(buf)
+    }
+
+    /// Attempts to write a tilemap into the start of a byte slice,
+    /// returning how many bytes were written.
+    ///
+    /// This lets a tilemap be written into a fixed buffer without allocating one for the output.
+    /// If the tilemap doesn't fit, the contents of the slice are unspecified.
+    ///
+    /// # Errors
+    /// The tilemap doesn't fit in the slice, raising [`io::ErrorKind::WriteZero`],
+    /// or it holds data that its version can't store.
+    pub fn write_to_slice(&self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        let len = buf.len();
+        let mut remaining = buf;
+        self.write(&mut remaining)?;
+        Ok(len - remaining.len())
+    }
+
+    /// Estimate how many bytes the tilemap takes up in a file, without compression.
+    fn estimated_size(&self) -> usize {
+        // Magic string, version, and a block header for each kind of block
+        let mut size = 10 + 3 * 8;
+        for (key, value) in &self.properties {
+            size += 1 + key.len() + 1 + 4;
+            if let Property::String(string) = value {
+                size += string.len();
+            }
+        }
+        for tileset in &self.tilesets {
+            size += 4 + 1 + tileset.path.len();
+        }
+        for layer in &self.layers {
+            // The tiles are in a block of their own, with an ID and a length
+            size += min_layer_size(self.version) as usize + 8 + layer.data.len() * 2;
+            for sublayer in &layer.sublayers {
+                // Block ID, cell size, default value and length
+                size += 4 + 1 + sublayer.cell_size as usize + 4 + sublayer.data.len();
+            }
+        }
+        for (_, contents) in &self.unknown_blocks {
+            size += 8 + contents.len();
+        }
+        size
+    }
+
+    /// Attempt to read everything but the tiles and sublayers of a tilemap from a seekable readable.
+    ///
+    /// The compressed data of each layer is seeked past instead of being decoded,
+    /// and can be loaded afterwards with [`TileMapHeader::load_layer`].
+    ///
+    /// # Errors
+    /// Errors if the file fails to be read.
+    pub fn read_header_only<R: Read + Seek>(mut cursor: R) -> Result<TileMapHeader, ReadError> {
+        let start = cursor.stream_position()?;
+        let len = cursor.seek(SeekFrom::End(0))?.saturating_sub(start);
+        cursor.seek(SeekFrom::Start(start))?;
+        let mut layers = Vec::new();
+        let mut cursor = read_helper::Counter::new(cursor);
+        cursor.end = Some(len);
+        let on_block = |_, _, _| {};
+        let tilemap = Self::read_blocks(
+            &mut cursor,
+            &ReadOptions::default(),
+            on_block,
+            |cursor, header, (index, total)| {
+                if index == 0 {
+                    layers.reserve(total);
+                }
+                skip_layer_data(cursor, len)?;
+                layers.push(header);
+                Ok(())
+            },
+        )?;
+        Ok(TileMapHeader {
+            version: tilemap.version,
+            version_flags: tilemap.version_flags,
+            layers,
+            tilesets: tilemap.tilesets,
+            properties: tilemap.properties,
+            unknown_blocks: tilemap.unknown_blocks,
+            start,
+        })
+    }
+
+    /// Read the blocks of a tilemap, telling `on_block` about each block header,
+    /// and handing each layer's metadata off to `on_layer` along with its index in its block
+    /// and the number of layers in that block,
+    /// with the cursor at the start of that layer's data blocks.
+    fn read_blocks<R: Read>(
+        cursor: &mut read_helper::Counter<R>,
+        options: &ReadOptions,
+        mut on_block: impl FnMut([u8; 4], u32, u64),
+        on_layer: impl FnMut(
+            &mut read_helper::Counter<R>,
+            LayerHeader,
+            (usize, usize),
+        ) -> Result<(), ReadError>,
+    ) -> Result<Self, ReadError> {
+        // Keep track of the block being read, to say where the stream ended if it ends early
+        let mut block = None;
+        let on_block = |id, size, offset| {
+            block = Some((id, offset));
+            on_block(id, size, offset);
+        };
+        Self::read_blocks_untracked(cursor, options, on_block, on_layer)
+            .map_err(|err| err.in_block(block))
+    }
+
+    /// Read the blocks of a tilemap, like [`TileMap::read_blocks`],
+    /// but without turning running out of bytes into [`ReadError::UnexpectedEof`].
+    fn read_blocks_untracked<R: Read>(
+        cursor: &mut read_helper::Counter<R>,
+        options: &ReadOptions,
+        mut on_block: impl FnMut([u8; 4], u32, u64),
+        mut on_layer: impl FnMut(
+            &mut read_helper::Counter<R>,
+            LayerHeader,
+            (usize, usize),
+        ) -> Result<(), ReadError>,
+    ) -> Result<Self, ReadError> {
+        // Read the magic string, see if it matches
+        let mut buf = [0; 8];
+        cursor.read_exact(&mut buf)?;
+        if &buf != b"ACHTUNG!" {
+            return Err(ReadError::InvalidMagic);
+        }
+        // The version is followed by a byte that's usually 1, for whatever reason
+        // We keep it separately here
+        let version = cursor.read_u8()? as u16;
+        let version_flags = cursor.read_u8()?;
+        if version > 5 {
+            return Err(ReadError::UnsupportedVersion(version));
+        }
+        let mut tilemap = TileMap {
+            version,
+            version_flags,
+            ..TileMap::default()
+        };
+        let mut global_dimensions = (16, 16);
+        loop {
+            let block_offset = cursor.position;
+            let mut block_header = [0; 8];
+            let read = read_helper::read_up_to(&mut *cursor, &mut block_header)?;
+            if read == 0 {
+                // Reached EOF, stop
+                break;
+            }
+            if read < block_header.len() {
+                // Too short to be a block, so this is padding at the end of the file
+                if options.strict {
+                    let header = String::from_utf8_lossy(&block_header[..read.min(4)]).into_owned();
+                    return Err(ReadError::InvalidHeader {
+                        header,
+                        offset: block_offset,
+                    });
+                }
+                // The padding isn't part of any block, so it doesn't count as read
+                cursor.position = block_offset;
+                break;
+            }
+            let [a, b, c, d, size @ ..] = block_header;
+            let block_id = [a, b, c, d];
+            // Block size is of no use to us, unless we don't know the block
+            let block_size = u32::from_le_bytes(size);
+            on_block(block_id, block_size, block_offset);
+            let truncated = ReadError::TruncatedBlock {
+                block: block_id,
+                offset: block_offset,
+            };
+            let body_start = cursor.position;
+            match &block_id {
+                b"MAP " => {
+                    // Mapping of strings to arbitrary data
+                    if version >= 3 {
+                        let count = cursor.read_u16::<LittleEndian>()?;
+                        // A name of at least one byte, a type, and a value of at least 4 bytes
+                        if !cursor.has_room(count as u64, 7) {
+                            return Err(truncated);
+                        }
+                        // Without std, properties are kept in a `BTreeMap`, which can't reserve
+                        #[cfg(feature = "std")]
+                        tilemap.properties.reserve(count as usize);
+                        for _ in 0..count {
+                            let name = read_helper::read_short_string(&mut *cursor)?;
+                            let offset = cursor.position;
+                            let ty = cursor.read_u8()?;
+                            let property = match ty {
+                                // Integer
+                                0 => match cursor.read_i32::<LittleEndian>()? {
+                                    0 if options.coerce_bools => Property::Boolean(false),
+                                    1 if options.coerce_bools => Property::Boolean(true),
+                                    int => Property::Integer(int),
+                                },
+                                1 => Property::Float(cursor.read_f32::<LittleEndian>()?),
+                                2 => Property::String(read_helper::read_long_string(&mut *cursor)?),
+                                ty => return Err(ReadError::InvalidType { ty, offset }),
+                            };
+                            let (key, lossy) = read_helper::decode_string(&name, options);
+                            if lossy {
+                                tilemap.lossy_strings.insert(key.clone(), name);
+                            }
+                            match (tilemap.properties.entry(key), options.on_duplicate_property) {
+                                (Entry::Vacant(entry), _) => {
+                                    entry.insert(property);
+                                }
+                                (Entry::Occupied(mut entry), DuplicatePolicy::KeepLast) => {
+                                    entry.insert(property);
+                                }
+                                (Entry::Occupied(_), DuplicatePolicy::KeepFirst) => {}
+                                (Entry::Occupied(entry), DuplicatePolicy::Error) => {
+                                    return Err(ReadError::DuplicateProperty(entry.key().clone()));
+                                }
+                            }
+                        }
+                    } else {
+                        // Deprecated, only in older versions
+                        global_dimensions = (
+                            cursor.read_u16::<LittleEndian>()?,
+                            cursor.read_u16::<LittleEndian>()?,
+                        );
+                    }
+                }
+                b"TILE" => {
+                    let amount = cursor.read_u8()?;
+                    // A color, and a path of at least one byte
+                    if !cursor.has_room(amount as u64, 6) {
+                        return Err(truncated);
+                    }
+                    tilemap.tilesets.reserve(amount as usize);
+                    for _ in 0..amount {
+                        // Color is stored in xBGR
+                        let mut buf = [0; 4];
+                        cursor.read_exact(&mut buf)?;
+                        let raw_path = read_helper::read_short_string(&mut *cursor)?;
+                        let (path, lossy) = read_helper::decode_string(&raw_path, options);
+                        if lossy {
+                            tilemap.lossy_strings.insert(path.clone(), raw_path);
+                        }
+                        tilemap.tilesets.push(TileSet {
+                            path,
+                            transparent_color: (buf[3], buf[2], buf[1]),
+                        });
+                    }
+                }
+                b"LAYR" => {
+                    let amount = if version == 0 {
+                        cursor.read_u8()? as u16
+                    } else {
+                        cursor.read_u16::<LittleEndian>()?
+                    };
+                    if !cursor.has_room(amount as u64, min_layer_size(version)) {
+                        return Err(truncated);
+                    }
+                    for index in 0..amount as usize {
+                        let size_offset = cursor.position;
+                        let (width, height) = (
+                            cursor.read_u32::<LittleEndian>()?,
+                            cursor.read_u32::<LittleEndian>()?,
+                        );
+                        // This is checked up front, so nothing that uses the size has to
+                        AreaOverflow::check(width, height).map_err(|_| {
+                            ReadError::InvalidLayerLength {
+                                offset: size_offset,
+                            }
+                        })?;
+                        let tile_dimensions = if version >= 2 {
+                            (
+                                cursor.read_u16::<LittleEndian>()?,
+                                cursor.read_u16::<LittleEndian>()?,
+                            )
+                        } else {
+                            // Read global dimensions
+                            global_dimensions
+                        };
+                        // Python struct syntax: =2B2i2f3?f
+                        let mut header = LayerHeader {
+                            width,
+                            height,
+                            tileset: cursor.read_u8()?,
+                            collision: cursor.read_u8()?,
+                            offset: (
+                                cursor.read_i32::<LittleEndian>()?,
+                                cursor.read_i32::<LittleEndian>()?,
+                            ),
+                            scroll: (
+                                cursor.read_f32::<LittleEndian>()?,
+                                cursor.read_f32::<LittleEndian>()?,
+                            ),
+                            wrap: (cursor.read_u8()? > 0, cursor.read_u8()? > 0),
+                            visible: cursor.read_u8()? > 0,
+                            opacity: cursor.read_f32::<LittleEndian>()?,
+                            tile_dimensions,
+                            sublayer_link: SubLayerLink::default(),
+                            data_offset: 0,
+                        };
+                        // Read sublayer link
+                        if version >= 4 {
+                            header.sublayer_link.tileset = cursor.read_u8()?;
+                            header.sublayer_link.animation = cursor.read_u8()?;
+                            if version == 5 {
+                                header.sublayer_link.animation_frame = cursor.read_u8()?;
+                            }
+                        }
+                        if options.clamp_floats {
+                            sanitize_floats(&mut header.opacity, &mut header.scroll);
+                        }
+                        header.data_offset = cursor.position;
+                        on_layer(&mut *cursor, header, (index, amount as usize))?;
+                    }
+                }
+                header if options.strict => {
+                    let header = String::from_utf8_lossy(header).into_owned();
+                    return Err(ReadError::InvalidHeader {
+                        header,
+                        offset: block_offset,
+                    });
+                }
+                _ => {
+                    let contents = read_helper::read_block(&mut *cursor, block_size)?;
+                    tilemap.unknown_blocks.push((block_id, contents));
+                }
+            }
+            let actual = cursor.position - body_start;
+            if options.validate_block_sizes && actual != block_size as u64 {
+                return Err(ReadError::BlockSizeMismatch {
+                    block: block_id,
+                    expected: block_size,
+                    actual,
+                    offset: block_offset,
+                });
+            }
+        }
+        Ok(tilemap)
+    }
+
+    /// Attempts to write a tilemap to a writable, using the tilemap's [`version`](TileMap::version).
+    ///
+    /// Any layers, tilesets, properties or sublayers past the limits of the file format
+    /// are silently left out. Use [`TileMap::write_checked`] to raise an error instead.
+    ///
+    /// # Errors
+    /// The file failed to be written,
+    /// or the tilemap holds data that its version can't store.
+    pub fn write(&self, cursor: impl Write) -> Result<(), io::Error> {
+        self.write_with_options(cursor, &WriteOptions::default())
+            .map_err(io::Error::from)
+    }
+
+    /// Attempts to write a tilemap to a writable, using the tilemap's [`version`](TileMap::version),
+    /// raising [`WriteError::LimitExceeded`] before writing anything
+    /// if there are more of something than the file format can store.
+    ///
+    /// # Errors
+    /// Errors if a collection is over its limit, if the tilemap holds data
+    /// that its version can't store, or if the file failed to be written.
+    pub fn write_checked(&self, cursor: impl Write) -> Result<(), WriteError> {
+        let max_layers = if self.version == 0 {
+            u8::MAX as usize
+        } else {
+            u16::MAX as usize
+        };
+        let limits = [
+            (LimitKind::Layers, self.layers.len(), max_layers),
+            (LimitKind::Tilesets, self.tilesets.len(), u8::MAX as usize),
+            (
+                LimitKind::Properties,
+                self.properties.len(),
+                u16::MAX as usize,
+            ),
+        ];
+        let sublayers = self.layers.iter().enumerate().map(|(index, layer)| {
+            (
+                LimitKind::Sublayers(index),
+                layer.sublayers.len(),
+                MAX_SUBLAYERS,
+            )
+        });
+        if let Some((kind, count, max)) = limits
+            .into_iter()
+            .chain(sublayers)
+            .find(|(_, count, max)| count > max)
+        {
+            return Err(WriteError::LimitExceeded { kind, count, max });
+        }
+        self.write_with_options(cursor, &WriteOptions::default())
+    }
+
+    /// Returns every property of the tilemap, sorted by key.
+    ///
+    /// Iterating over [`properties`](TileMap::properties) directly gives them in a random order,
+    /// so this is for when the order needs to be the same every time, like when printing them.
+    #[must_use]
+    pub fn properties_sorted(&self) -> Vec<(&str, &Property)> {
+        let mut properties = self
+            .properties
+            .iter()
+            .map(|(key, value)| (key.as_str(), value))
+            .collect::<Vec<_>>();
+        properties.sort_unstable_by_key(|(key, _)| *key);
+        properties
+    }
+
+    /// Returns the integer property with the given key, if there is one.
+    #[must_use]
+    pub fn get_int(&self, key: &str) -> Option<i32> {
+        self.properties.get(key).and_then(Property::as_i32)
+    }
+
+    /// Returns the float property with the given key, if there is one.
+    #[must_use]
+    pub fn get_float(&self, key: &str) -> Option<f32> {
+        self.properties.get(key).and_then(Property::as_f32)
+    }
+
+    /// Returns the string property with the given key, if there is one and it's valid UTF-8.
+    #[must_use]
+    pub fn get_string(&self, key: &str) -> Option<&str> {
+        self.properties.get(key).and_then(Property::as_str)
+    }
+
+    /// Set an integer property, returning the property that was there before.
+    pub fn set_int(&mut self, key: impl Into<String>, value: i32) -> Option<Property> {
+        self.properties.insert(key.into(), Property::Integer(value))
+    }
+
+    /// Set a float property, returning the property that was there before.
+    pub fn set_float(&mut self, key: impl Into<String>, value: f32) -> Option<Property> {
+        self.properties.insert(key.into(), Property::Float(value))
+    }
+
+    /// Set a string property, returning the property that was there before.
+    ///
+    /// Remember that empty strings fail to write!
+    pub fn set_string(
+        &mut self,
+        key: impl Into<String>,
+        value: impl Into<Vec<u8>>,
+    ) -> Option<Property> {
+        self.properties
+            .insert(key.into(), Property::String(value.into()))
+    }
+
+    /// Remove a property, returning it if it was there.
+    pub fn remove_property(&mut self, key: &str) -> Option<Property> {
+        self.properties.remove(key)
+    }
+
+    /// Replace the ID of every tile in every layer with the result of a function,
+    /// as with [`Layer::remap_ids`].
+    pub fn remap_all(&mut self, mut f: impl FnMut(u16) -> u16) {
+        for layer in &mut self.layers {
+            layer.remap_ids(&mut f);
+        }
+    }
+
+    /// Sort the tilesets by their paths, keeping the order of tilesets with equal paths,
+    /// and update the layers to keep pointing at the same tilesets.
+    ///
+    /// This rewrites each layer's [`tileset`](Layer::tileset),
+    /// as well as the cells of its [linked tileset sublayer](SubLayerLink::tileset)
+    /// if that has one byte per cell. Indices that are unset or out of range are left alone.
+    pub fn sort_tilesets_by_path(&mut self) {
+        let mut order = (0..self.tilesets.len()).collect::<Vec<_>>();
+        order.sort_by(|&a, &b| self.tilesets[a].path.cmp(&self.tilesets[b].path));
+        // Map each old index to where its tileset ends up
+        let mut new_indices = vec![0; order.len()];
+        for (new, &old) in order.iter().enumerate() {
+            new_indices[old] = new;
+        }
+        for layer in &mut self.layers {
+            layer.remap_tilesets(|index| {
+                let new = *new_indices.get(index as usize)?;
+                u8::try_from(new).ok()
+            });
+        }
+        let mut tilesets = core::mem::take(&mut self.tilesets)
+            .into_iter()
+            .map(Some)
+            .collect::<Vec<_>>();
+        self.tilesets = order
+            .into_iter()
+            .filter_map(|old| tilesets[old].take())
+            .collect();
+    }
+
+    /// Move the layers of another tilemap onto the end of this one's,
+    /// merging in its properties.
+    ///
+    /// If `remap_tilesets` is set, the other tilemap's tilesets are appended to this one's,
+    /// and the incoming layers are updated to keep pointing at them,
+    /// as with [`TileMap::sort_tilesets_by_path`].
+    /// Otherwise, the other tilemap's tilesets are dropped.
+    /// Incoming indices that were already out of range are shifted too,
+    /// becoming unset (`0xFF`) if they'd go past what can be stored.
+    ///
+    /// Properties already in this tilemap are kept over the other's,
+    /// and the keys of those that collided are returned, sorted.
+    ///
+    /// # Errors
+    /// Errors without changing either tilemap if `remap_tilesets` is set,
+    /// and there would be more than 255 tilesets between the two.
+    pub fn append(
+        &mut self,
+        other: TileMap,
+        remap_tilesets: bool,
+    ) -> Result<Vec<String>, TooManyTilesets> {
+        let mut layers = other.layers;
+        if remap_tilesets {
+            let offset = self.tilesets.len();
+            let count = offset + other.tilesets.len();
+            if count > u8::MAX as usize {
+                return Err(TooManyTilesets { count });
+            }
+            for layer in &mut layers {
+                layer.remap_tilesets(|index| {
+                    Some(u8::try_from(index as usize + offset).unwrap_or(0xFF))
+                });
+            }
+            self.tilesets.extend(other.tilesets);
+        }
+        self.layers.append(&mut layers);
+        let mut collisions = Vec::new();
+        for (key, value) in other.properties {
+            match self.properties.entry(key) {
+                Entry::Occupied(entry) => collisions.push(entry.key().clone()),
+                Entry::Vacant(entry) => {
+                    entry.insert(value);
+                }
+            }
+        }
+        for (string, bytes) in other.lossy_strings {
+            self.lossy_strings.entry(string).or_insert(bytes);
+        }
+        collisions.sort_unstable();
+        Ok(collisions)
+    }
+
+    /// Keep only the layers for which the function returns `true`, in their order.
+    ///
+    /// For example, `map.retain_layers(|layer| layer.visible)` removes hidden layers.
+    pub fn retain_layers(&mut self, f: impl FnMut(&Layer) -> bool) {
+        self.layers.retain(f);
+    }
+
+    /// Keep only the properties for which the function returns `true`,
+    /// given their key and value.
+    pub fn retain_properties(&mut self, mut f: impl FnMut(&str, &Property) -> bool) {
+        self.properties.retain(|key, value| f(key, value));
+    }
+
+    /// Remove every [empty](Layer::is_empty) layer, returning how many were removed.
+    pub fn remove_empty_layers(&mut self) -> usize {
+        let before = self.layers.len();
+        self.layers.retain(|layer| !layer.is_empty());
+        before - self.layers.len()
+    }
+
+    /// Look up the tileset a layer uses.
+    ///
+    /// Returns `None` if the layer's tileset is unset (`0xFF`) or out of range.
+    #[must_use]
+    pub fn tileset_for_layer(&self, layer: &Layer) -> Option<&TileSet> {
+        if layer.tileset == 0xFF {
+            return None;
+        }
+        self.tilesets.get(layer.tileset as usize)
+    }
+
+    /// Returns the bytes a property key or tileset path was read from.
+    ///
+    /// This is only different from the string's own bytes if they couldn't be decoded exactly,
+    /// in which case the string holds replacement characters.
+    ///
+    /// ```rust
+    /// # use ct_tilemap::TileMap;
+    /// let map = TileMap::new();
+    /// assert_eq!(map.original_bytes("tiles.png"), b"tiles.png");
+    /// ```
+    #[must_use]
+    pub fn original_bytes<'a>(&'a self, string: &'a str) -> &'a [u8] {
+        self.lossy_strings
+            .get(string)
+            .map_or(string.as_bytes(), Vec::as_slice)
+    }
+
+    /// Creates an iterator over every sublayer of every layer,
+    /// along with the index of its layer and its index within that layer.
+    pub fn iter_sublayers(&self) -> impl Iterator<Item = (usize, usize, &SubLayer)> {
+        self.layers
+            .iter()
+            .enumerate()
+            .flat_map(|(layer_index, layer)| {
+                layer
+                    .sublayers
+                    .iter()
+                    .enumerate()
+                    .map(move |(index, sublayer)| (layer_index, index, sublayer))
+            })
+    }
+
+    /// Creates an iterator over every sublayer of every layer, mutably,
+    /// along with the index of its layer and its index within that layer.
+    pub fn iter_sublayers_mut(&mut self) -> impl Iterator<Item = (usize, usize, &mut SubLayer)> {
+        self.layers
+            .iter_mut()
+            .enumerate()
+            .flat_map(|(layer_index, layer)| {
+                layer
+                    .sublayers
+                    .iter_mut()
+                    .enumerate()
+                    .map(move |(index, sublayer)| (layer_index, index, sublayer))
+            })
+    }
+
+    /// Creates an iterator over the path of each tileset, along with its index.
+    pub fn tileset_paths(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.tilesets
+            .iter()
+            .map(|tileset| tileset.path.as_str())
+            .enumerate()
+    }
+
+    /// Count how many times each tile ID appears across all layers,
+    /// leaving out the [`empty_tile`](TileMap::empty_tile).
+    ///
+    /// This is useful for finding which tileset slots are actually used.
+    #[must_use]
+    pub fn tile_census(&self) -> HashMap<u16, usize> {
+        let mut census = HashMap::new();
+        for layer in &self.layers {
+            for tile in layer.iter().filter(|tile| **tile != self.empty_tile) {
+                *census.entry(tile.id()).or_insert(0) += 1;
+            }
+        }
+        census
+    }
+
+    /// Count the tiles across all layers that aren't the [`empty_tile`](TileMap::empty_tile).
+    #[must_use]
+    pub fn non_empty_count(&self) -> usize {
+        self.layers
+            .iter()
+            .map(|layer| {
+                layer
+                    .iter()
+                    .filter(|tile| **tile != self.empty_tile)
+                    .count()
+            })
+            .sum()
+    }
+
+    /// Returns whether two tilemaps hold the same content,
+    /// unlike `==` ignoring the metadata of their layers, their versions and their unknown blocks.
+    ///
+    /// Layers are compared in order with [`Layer::same_tiles`], along with their sublayers.
+    /// Properties are compared regardless of the order they were inserted in.
+    #[must_use]
+    pub fn same_content(&self, other: &TileMap) -> bool {
+        self.layers.len() == other.layers.len()
+            && self
+                .layers
+                .iter()
+                .zip(&other.layers)
+                .all(|(a, b)| a.same_tiles(b) && a.sublayers == b.sublayers)
+            && self.tilesets == other.tilesets
+            && self.properties == other.properties
+    }
+
+    /// Summarize what the tilemap holds.
+    ///
+    /// Tiles are counted as empty if they're the [`empty_tile`](TileMap::empty_tile).
+    #[must_use]
+    pub fn stats(&self) -> TileMapStats {
+        let mut stats = TileMapStats {
+            layers: self.layers.len(),
+            tilesets: self.tilesets.len(),
+            properties: self.properties.len(),
+            non_empty_tiles: self.non_empty_count(),
+            ..TileMapStats::default()
+        };
+        for layer in &self.layers {
+            stats.tiles += layer.data.len();
+            stats.sublayer_bytes += layer
+                .sublayers
+                .iter()
+                .map(|sublayer| sublayer.data.len())
+                .sum::<usize>();
+            stats.bounds.0 = stats.bounds.0.max(layer.width);
+            stats.bounds.1 = stats.bounds.1.max(layer.height);
+        }
+        stats
+    }
+
+    /// Returns whether the tilemap has no layers, tilesets, properties or unknown blocks,
+    /// so writing it would only write the magic string and version.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+            && self.tilesets.is_empty()
+            && self.properties.is_empty()
+            && self.unknown_blocks.is_empty()
+    }
+
+    /// Returns the tile that counts as empty in this tilemap,
+    /// which is the tile default (`0xFFFF`) unless changed with [`TileMap::set_empty_tile`].
+    ///
+    /// This isn't stored in files.
+    /// [`TileMap::non_empty_count`], [`TileMap::stats`], [`TileMap::tile_census`]
+    /// and [`TileMap::flatten_visible`] go by it, whatever the layers' own default tiles are.
+    #[must_use]
+    pub fn empty_tile(&self) -> Tile {
+        self.empty_tile
+    }
+
+    /// Set the tile that counts as empty in this tilemap,
+    /// for projects that mark missing tiles with something other than the tile default.
+    ///
+    /// This also sets the [`default_tile`](Layer::default_tile) of every layer to it,
+    /// so resizing, clearing, trimming and counting the tiles of those layers go by it too.
+    /// Layers added afterwards keep their own default tile,
+    /// but the map-wide methods listed under [`TileMap::empty_tile`] still go by this one.
+    pub fn set_empty_tile(&mut self, tile: Tile) {
+        self.empty_tile = tile;
+        for layer in &mut self.layers {
+            layer.default_tile = tile;
+        }
+    }
+
+    /// Composite the visible layers into a single layer, for previewing the tilemap.
+    ///
+    /// Only layers that are [`visible`](Layer::visible), aren't fully transparent,
+    /// and have the same [`tile_dimensions`](Layer::tile_dimensions) as the first of them are used.
+    /// Later layers are drawn over earlier ones, with the [`empty_tile`](TileMap::empty_tile)
+    /// letting the tiles under it show through, and filling the gaps between the layers.
+    ///
+    /// Each layer is placed at its [`offset`](Layer::offset), rounded down to whole tiles,
+    /// and the result is just big enough to hold all of them, with its offset at the top left corner.
+    /// Layers far apart from each other make for a large result!
+    /// Sublayers and other layer settings aren't carried over.
+    #[must_use]
+    pub fn flatten_visible(&self) -> Layer {
+        let mut layers = self
+            .layers
+            .iter()
+            .filter(|layer| layer.visible && layer.opacity != 0.0)
+            .peekable();
+        let Some(tile_dimensions) = layers.peek().map(|layer| layer.tile_dimensions) else {
+            return Layer::new();
+        };
+        let layers = layers
+            .filter(|layer| layer.tile_dimensions == tile_dimensions)
+            .collect::<Vec<_>>();
+        let (tile_w, tile_h) = (
+            i64::from(tile_dimensions.0.max(1)),
+            i64::from(tile_dimensions.1.max(1)),
+        );
+        // Where each layer's top left corner is, in tiles
+        let origins = layers
+            .iter()
+            .map(|layer| {
+                (
+                    i64::from(layer.offset.0).div_euclid(tile_w),
+                    i64::from(layer.offset.1).div_euclid(tile_h),
+                )
+            })
+            .collect::<Vec<_>>();
+        let min_x = origins.iter().map(|origin| origin.0).min().unwrap_or(0);
+        let min_y = origins.iter().map(|origin| origin.1).min().unwrap_or(0);
+        let max_x = layers
+            .iter()
+            .zip(&origins)
+            .map(|(layer, origin)| origin.0 + i64::from(layer.width))
+            .max()
+            .unwrap_or(0);
+        let max_y = layers
+            .iter()
+            .zip(&origins)
+            .map(|(layer, origin)| origin.1 + i64::from(layer.height))
+            .max()
+            .unwrap_or(0);
+        let to_size = |value: i64| u32::try_from(value).unwrap_or(u32::MAX);
+        let to_offset = |value: i64| {
+            i32::try_from(value).unwrap_or(if value < 0 { i32::MIN } else { i32::MAX })
+        };
+        let mut flattened = Layer {
+            tile_dimensions,
+            offset: (to_offset(min_x * tile_w), to_offset(min_y * tile_h)),
+            default_tile: self.empty_tile,
+            ..Layer::default()
+        };
+        flattened.resize(to_size(max_x - min_x), to_size(max_y - min_y));
+        for (layer, origin) in layers.into_iter().zip(origins) {
+            flattened.blit_skipping(
+                layer,
+                to_size(origin.0 - min_x),
+                to_size(origin.1 - min_y),
+                Some(self.empty_tile),
+            );
+        }
+        flattened
+    }
+
+    /// Find what changed from this tilemap to another.
+    ///
+    /// Layers that are equal in both are matched up first, keeping their order,
+    /// so inserting or removing a layer doesn't make every layer after it count as modified.
+    /// The layers left over between matches are paired up in order as modified,
+    /// and any left over after that were added or removed.
+    #[must_use]
+    pub fn diff(&self, other: &TileMap) -> TileMapDiff {
+        let mut diff = TileMapDiff::default();
+        let matches = match_layers(&self.layers, &other.layers);
+        let (mut old, mut new) = (0, 0);
+        // The end of both acts as one last match, to handle what's left after the real ones
+        for (old_end, new_end) in matches
+            .into_iter()
+            .chain(iter::once((self.layers.len(), other.layers.len())))
+        {
+            let paired = (old_end - old).min(new_end - new);
+            for offset in 0..paired {
+                let (old_index, new_index) = (old + offset, new + offset);
+                diff.modified_layers.push(LayerDiff::new(
+                    (old_index, &self.layers[old_index]),
+                    (new_index, &other.layers[new_index]),
+                ));
+            }
+            diff.removed_layers.extend(old + paired..old_end);
+            diff.added_layers.extend(new + paired..new_end);
+            (old, new) = (old_end + 1, new_end + 1);
+        }
+        for (key, value) in &self.properties {
+            let new_value = other.properties.get(key);
+            if new_value != Some(value) {
+                diff.properties
+                    .insert(key.clone(), (Some(value.clone()), new_value.cloned()));
+            }
+        }
+        for (key, value) in &other.properties {
+            if !self.properties.contains_key(key) {
+                diff.properties
+                    .insert(key.clone(), (None, Some(value.clone())));
+            }
+        }
+        diff
+    }
+
+    /// Hash the layers, tilesets and properties of the tilemap with a fixed hasher,
+    /// suitable for caching things derived from its contents.
+    ///
+    /// Equal contents give equal hashes across runs of the program,
+    /// regardless of the order the properties were inserted in.
+    /// The hash is not guaranteed to be the same across platforms or versions of this crate.
+    #[must_use]
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = fnv::Fnv1a::default();
+        self.layers.hash(&mut hasher);
+        self.tilesets.hash(&mut hasher);
+        // Map iteration order is random, so the properties have to be sorted first
+        self.properties_sorted().hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Check whether anything in the tilemap would be truncated or rejected
+    /// when writing it with its [`version`](TileMap::version).
+    ///
+    /// # Errors
+    /// Returns every problem found, if there were any.
+    pub fn validate(&self) -> Result<(), Vec<ValidationWarning>> {
+        let mut warnings = Vec::new();
+        let max_layers = if self.version == 0 {
+            u8::MAX as usize
+        } else {
+            u16::MAX as usize
+        };
+        if self.layers.len() > max_layers {
+            warnings.push(ValidationWarning::TooManyLayers(self.layers.len()));
+        }
+        if self.tilesets.len() > u8::MAX as usize {
+            warnings.push(ValidationWarning::TooManyTilesets(self.tilesets.len()));
+        }
+        if self.properties.len() > u16::MAX as usize {
+            warnings.push(ValidationWarning::TooManyProperties(self.properties.len()));
+        }
+        // Sort the keys, so the warnings come out in a stable order
+        let mut keys = self.properties.keys().collect::<Vec<_>>();
+        keys.sort_unstable();
+        for key in keys {
+            if key.is_empty() {
+                warnings.push(ValidationWarning::EmptyPropertyKey);
+            } else if key.len() > 256 {
+                warnings.push(ValidationWarning::PropertyKeyTooLong(key.clone()));
+            }
+            if matches!(&self.properties[key], Property::String(s) if s.is_empty()) {
+                warnings.push(ValidationWarning::EmptyStringProperty(key.clone()));
+            }
+        }
+        for (index, layer) in self.layers.iter().enumerate() {
+            if layer.sublayers.len() > MAX_SUBLAYERS {
+                warnings.push(ValidationWarning::SublayerCountExceeded {
+                    layer: index,
+                    count: layer.sublayers.len(),
+                });
+            }
+        }
+        for (index, tileset) in self.tilesets.iter().enumerate() {
+            if tileset.path.is_empty() {
+                warnings.push(ValidationWarning::EmptyTilesetPath(index));
+            } else if tileset.path.len() > 256 {
+                warnings.push(ValidationWarning::TilesetPathTooLong(index));
+            }
+        }
+        if warnings.is_empty() {
+            Ok(())
+        } else {
+            Err(warnings)
+        }
+    }
+
+    /// Attempt to read a tilemap from the file at the given path.
+    ///
+    /// # Errors
+    /// Errors if the file can't be opened, or fails to be read.
+    #[cfg(feature = "std")]
+    pub fn read_file(path: impl AsRef<Path>) -> Result<Self, ReadError> {
+        let file = File::open(path)?;
+        let mut cursor = read_helper::Counter::new(BufReader::new(&file));
+        cursor.end = Some(file.metadata()?.len());
+        let options = ReadOptions::default();
+        let mut scratch = ReadScratch::new();
+        Self::read_into_scratch(
+            &mut cursor,
+            &options,
+            |_, _, _| {},
+            |_, _| {},
+            |_, _, _| {},
+            &mut scratch,
+        )
+    }
+
+    /// Attempts to write a tilemap to the file at the given path,
+    /// creating it if it doesn't exist and truncating it if it does.
+    ///
+    /// # Errors
+    /// Errors if the file can't be created, or fails to be written.
+    #[cfg(feature = "std")]
+    pub fn write_file(&self, path: impl AsRef<Path>) -> Result<(), io::Error> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        // Files can seek, so the blocks don't have to be buffered
+        self.write_seekable(&mut writer, &WriteOptions::default())?;
+        writer.flush()
+    }
+
+    /// Attempts to write a tilemap to a writable, using the block layout of the given file version.
+    ///
+    /// Versions below 3 store the tile dimensions of the first layer in the `MAP ` block,
+    /// in place of properties.
+    ///
+    /// # Errors
+    /// Errors if the version is unsupported, if the tilemap holds data
+    /// that the version can't store, or if the file failed to be written.
+    pub fn write_versioned(&self, cursor: impl Write, version: u16) -> Result<(), WriteError> {
+        self.write_with_options(
+            cursor,
+            &WriteOptions {
+                version: Some(version),
+                ..WriteOptions::default()
+            },
+        )
+    }
+
+    /// Attempts to write a tilemap to a writable without compressing its tile and sublayer data,
+    /// so that the bytes of each tile can be found in the file as they are.
+    ///
+    /// This is the same as writing with [`CompressionLevel::None`]. The data is still wrapped
+    /// in a zlib stream, so the file reads back like any other.
+    ///
+    /// # Errors
+    /// Errors if the tilemap holds data that its version can't store,
+    /// or if the file failed to be written.
+    pub fn write_uncompressed(&self, cursor: impl Write) -> Result<(), WriteError> {
+        self.write_with_options(
+            cursor,
+            &WriteOptions {
+                compression: CompressionLevel::None,
+                ..WriteOptions::default()
+            },
+        )
+    }
+
+    /// Attempts to write a tilemap to a writable, with the given options.
+    ///
+    /// # Errors
+    /// Errors if the version is unsupported, if the tilemap holds data
+    /// that the version can't store, or if the file failed to be written.
+    pub fn write_with_options(
+        &self,
+        cursor: impl Write,
+        options: &WriteOptions,
+    ) -> Result<(), WriteError> {
+        let indices = (0..self.layers.len()).collect::<Vec<_>>();
+        self.write_subset(write_helper::Buffered(cursor), &indices, options)
+    }
+
+    /// Attempts to write a tilemap to a seekable writable, with the given options.
+    ///
+    /// This writes the same bytes as [`TileMap::write_with_options`],
+    /// but streams each block straight to the writable and seeks back to fill in its length,
+    /// instead of buffering the whole block first.
+    ///
+    /// # Errors
+    /// Errors if the version is unsupported, if the tilemap holds data
+    /// that the version can't store, or if the file failed to be written.
+    pub fn write_seekable(
+        &self,
+        cursor: impl Write + Seek,
+        options: &WriteOptions,
+    ) -> Result<(), WriteError> {
+        let indices = (0..self.layers.len()).collect::<Vec<_>>();
+        self.write_subset(write_helper::Seekable(cursor), &indices, options)
+    }
+
+    /// Attempts to write a tilemap to a writable, with only the layers at the given indices,
+    /// in the order they're given.
+    ///
+    /// Everything else is written as it is by [`TileMap::write`],
+    /// so the result is a file of its own that can be read back.
+    ///
+    /// # Errors
+    /// Errors with [`WriteError::NoSuchLayer`] before writing anything if an index is out of range,
+    /// if the tilemap holds data that its version can't store, or if the file failed to be written.
+    pub fn write_layers(&self, cursor: impl Write, indices: &[usize]) -> Result<(), WriteError> {
+        if let Some(&index) = indices.iter().find(|&&index| index >= self.layers.len()) {
+            return Err(WriteError::NoSuchLayer(index));
+        }
+        self.write_subset(
+            write_helper::Buffered(cursor),
+            indices,
+            &WriteOptions::default(),
+        )
+    }
+
+    /// Encode a property key or tileset path to be written,
+    /// using its original bytes if it was decoded lossily.
+    fn encode_string<'a>(&'a self, string: &'a str, options: &WriteOptions) -> Cow<'a, [u8]> {
+        match self.lossy_strings.get(string) {
+            Some(bytes) => Cow::Borrowed(bytes),
+            None => write_helper::encode_string(string, options),
+        }
+    }
+
+    /// Write the tilemap with only the layers at the given indices, which must be in range.
+    fn write_subset(
+        &self,
+        mut cursor: impl write_helper::Sink,
+        indices: &[usize],
+        options: &WriteOptions,
+    ) -> Result<(), WriteError> {
+        let version = options.version.unwrap_or(self.version);
+        if version > 5 {
+            return Err(WriteError::UnsupportedVersion(version));
+        }
+        let layers = indices
+            .iter()
+            .map(|&index| &self.layers[index])
+            .collect::<Vec<_>>();
+        let global_dimensions = layers
+            .first()
+            .map_or((16, 16), |layer| layer.tile_dimensions);
+        // Check that everything fits before writing anything
+        if version < 3 && !self.properties.is_empty() {
+            return Err(WriteError::Unrepresentable(version, "properties"));
+        }
+        if options.strict_strings {
+            let too_long = |string: &str| self.encode_string(string, options).len() > 256;
+            if let Some(key) = self.properties.keys().find(|key| too_long(key)) {
+                return Err(WriteError::PropertyKeyTooLong(key.clone()));
+            }
+            if let Some(index) = self
+                .tilesets
+                .iter()
+                .position(|tileset| too_long(&tileset.path))
+            {
+                return Err(WriteError::TilesetPathTooLong(index));
+            }
+        }
+        for (&index, layer) in indices.iter().zip(&layers) {
+            if options.strict && !layer.is_consistent() {
+                return Err(WriteError::InconsistentLayer(index));
+            }
+            if version < 2 && layer.tile_dimensions != global_dimensions {
+                return Err(WriteError::Unrepresentable(
+                    version,
+                    "differing tile dimensions between layers",
+                ));
+            }
+            if version < 4 && !layer.sublayers.is_empty() {
+                return Err(WriteError::Unrepresentable(version, "sublayers"));
+            }
+            if version < 4 && layer.sublayer_link != SubLayerLink::default() {
+                return Err(WriteError::Unrepresentable(version, "sublayer links"));
+            }
+            if version == 4 && layer.sublayer_link.animation_frame != 0xFF {
+                return Err(WriteError::Unrepresentable(
+                    version,
+                    "animation frame sublayer links",
+                ));
+            }
+        }
+        // Write magic string
+        cursor.write_all(b"ACHTUNG!")?;
+        // The version has an extra byte
+        cursor.write_u8(version as u8)?;
+        cursor.write_u8(self.version_flags)?;
+        if version < 3 {
+            // Deprecated, only in older versions
+            let mut cur = Header::new(&mut cursor, b"MAP ")?;
+            cur.write_u16::<LittleEndian>(global_dimensions.0)?;
+            cur.write_u16::<LittleEndian>(global_dimensions.1)?;
+            cur.write_header()?;
+        } else if !self.properties.is_empty() {
+            let mut cur = Header::new(&mut cursor, b"MAP ")?;
+            // Can only store up to 65535 properties
+            cur.write_u16::<LittleEndian>(self.properties.len().min(u16::MAX as usize) as u16)?;
+            for (key, value) in self.properties.iter().take(0xFFFF) {
+                write_helper::write_short_string(&mut cur, &self.encode_string(key, options))?;
+                match value {
+                    Property::Integer(i) => {
+                        cur.write_u8(0)?; // Integer: 0
+                        cur.write_i32::<LittleEndian>(*i)?;
+                    }
+                    Property::Boolean(b) => {
+                        // Booleans are stored as integers
+                        cur.write_u8(0)?;
+                        cur.write_i32::<LittleEndian>(*b as i32)?;
+                    }
+                    Property::Float(f) => {
+                        cur.write_u8(1)?; // Float: 1
+                        cur.write_f32::<LittleEndian>(*f)?;
+                    }
+                    Property::String(s) => {
+                        cur.write_u8(2)?; // String: 2
+                        write_helper::write_long_string(&mut cur, s)?;
+                    }
+                }
+            }
+            cur.write_header()?;
+        }
+        if !self.tilesets.is_empty() {
+            let mut cur = Header::new(&mut cursor, b"TILE")?;
+            let len = self.tilesets.len().min(255) as u8;
+            cur.write_u8(len)?;
+            for tileset in self.tilesets.iter().take(0xFF) {
+                cur.write_u8(0)?; // Padding
+                cur.write_u8(tileset.transparent_color.2)?; // B
+                cur.write_u8(tileset.transparent_color.1)?; // G
+                cur.write_u8(tileset.transparent_color.0)?; // R
+                write_helper::write_short_string(
+                    &mut cur,
+                    &self.encode_string(&tileset.path, options),
+                )?;
+            }
+            cur.write_header()?;
+        }
+        if !layers.is_empty() {
+            // Can only store up to 255 layers in version 0, and 65535 after that
+            let max_layers = if version == 0 {
+                u8::MAX as usize
+            } else {
+                u16::MAX as usize
+            };
+            let layers = &layers[..layers.len().min(max_layers)];
+            let framing = options.framing.unwrap_or(self.framing);
+            let compress = |data: &[u8]| write_helper::encode(data, options.compression, framing);
+            // When writing in parallel, every block is compressed up front,
+            // so that they can be spread across threads.
+            // Otherwise, each block is converted and compressed right before it's written,
+            // so that only one of them is held in memory at a time
+            #[cfg(feature = "rayon")]
+            let precompressed = if options.parallel {
+                Some(write_helper::compress_layers(
+                    layers,
+                    options.compression,
+                    framing,
+                )?)
+            } else {
+                None
+            };
+            #[cfg(not(feature = "rayon"))]
+            let precompressed: Option<Vec<Vec<Vec<u8>>>> = None;
+
+            let mut cur = Header::new(&mut cursor, b"LAYR")?;
+            if version == 0 {
+                cur.write_u8(layers.len() as u8)?;
+            } else {
+                cur.write_u16::<LittleEndian>(layers.len() as u16)?;
+            }
+            let mut precompressed = precompressed.map(Vec::into_iter);
+            for layer in layers {
+                // The blocks compressed up front for this layer, holding its tiles and then its sublayers.
+                // There's a list of them for every layer, and one block for each that's written,
+                // so running out would be a bug rather than something to quietly write nothing for
+                let mut blocks = precompressed.as_mut().map(|layers| {
+                    layers
+                        .next()
+                        .expect("blocks should be compressed for every layer")
+                        .into_iter()
+                });
+                let mut next_block = || {
+                    blocks.as_mut().map(|blocks| {
+                        blocks
+                            .next()
+                            .expect("a block should be compressed for everything written")
+                    })
+                };
+                cur.write_u32::<LittleEndian>(layer.width)?;
+                cur.write_u32::<LittleEndian>(layer.height)?;
+                // Write layer settings
+                if version >= 2 {
+                    cur.write_u16::<LittleEndian>(layer.tile_dimensions.0)?;
+                    cur.write_u16::<LittleEndian>(layer.tile_dimensions.1)?;
+                }
+                cur.write_u8(layer.tileset)?;
+                cur.write_u8(layer.collision)?;
+                cur.write_i32::<LittleEndian>(layer.offset.0)?;
+                cur.write_i32::<LittleEndian>(layer.offset.1)?;
+                cur.write_f32::<LittleEndian>(layer.scroll.0)?;
+                cur.write_f32::<LittleEndian>(layer.scroll.1)?;
+                cur.write_u8(layer.wrap.0 as u8)?;
+                cur.write_u8(layer.wrap.1 as u8)?;
+                cur.write_u8(layer.visible as u8)?;
+                cur.write_f32::<LittleEndian>(layer.opacity)?;
+                // Write sublayer link
+                if version >= 4 {
+                    cur.write_u8(layer.sublayer_link.tileset)?;
+                    cur.write_u8(layer.sublayer_link.animation)?;
+                    if version == 5 {
+                        cur.write_u8(layer.sublayer_link.animation_frame)?;
+                    }
+                }
+                if layer.is_empty() {
+                    // Empty layer
+                    cur.write_u8(0)?; // Layer size
+                    continue;
+                }
+                // Number of headers in this section
+                // Add one for the main header
+                let sublayer_count = layer.sublayers.len().min(MAX_SUBLAYERS);
+                cur.write_u8(sublayer_count as u8 + 1)?;
+                let main = match next_block() {
+                    Some(block) => block,
+                    None => compress(&write_helper::tile_bytes(layer))?,
+                };
+                cur.write_all(b"MAIN")?;
+                write_helper::write_compressed(&mut cur, &main)?;
+                // Only one compressed block is held at a time
+                drop(main);
+                for sublayer in layer.sublayers.iter().take(sublayer_count) {
+                    let data = match next_block() {
+                        Some(block) => block,
+                        None => compress(&sublayer.data)?,
+                    };
+                    cur.write_all(b"DATA")?;
+                    cur.write_u8(sublayer.cell_size)?;
+                    cur.write_all(&sublayer.default_value)?;
+                    write_helper::write_compressed(&mut cur, &data)?;
+                }
+            }
+            cur.write_header()?;
+        }
+        for (id, contents) in &self.unknown_blocks {
+            let mut cur = Header::new(&mut cursor, id)?;
+            cur.write_all(contents)?;
+            cur.write_header()?;
+        }
+        Ok(())
+    }
+
+    /// Constructs a new instance from the default.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Clamp an opacity into `0.0..=1.0` and replace non-finite scrolls with `0.0`,
+/// as done by [`Layer::sanitize`].
+fn sanitize_floats(opacity: &mut f32, scroll: &mut (f32, f32)) {
+    *opacity = if opacity.is_nan() {
+        1.0
+    } else {
+        opacity.clamp(0.0, 1.0)
+    };
+    for axis in [&mut scroll.0, &mut scroll.1] {
+        if !axis.is_finite() {
+            *axis = 0.0;
+        }
+    }
+}
+
+/// The fewest bytes a layer can take up in the `LAYR` block of a file with this version.
+fn min_layer_size(version: u16) -> u64 {
+    // Size, tileset, collision, offset, scroll, wrap, visibility, opacity and data block count
+    let mut size = 8 + 1 + 1 + 8 + 8 + 2 + 1 + 4 + 1;
+    if version >= 2 {
+        // Tile dimensions
+        size += 4;
+    }
+    if version >= 4 {
+        // Sublayer link
+        size += if version == 5 { 3 } else { 2 };
+    }
+    size
+}
+
+/// Read the data blocks of a layer into it,
+/// returning [`Framing::RawDeflate`] if any of them were framed that way.
+fn read_layer_data<R: Read>(
+    cursor: &mut read_helper::Counter<R>,
+    layer: &mut Layer,
+    options: &ReadOptions,
+    scratch: &mut ReadScratch,
+    mut on_raw_block: impl FnMut(BlockKind, &[u8]),
+) -> Result<Framing, ReadError> {
+    let mut framing = Framing::Zlib;
+    let limit = options.max_decompressed_size;
+    // Skipped sublayers aren't added to the layer, so they're counted separately
+    let mut data_index = 0;
+    let too_large = |offset| ReadError::DecompressedTooLarge { limit, offset };
+    // Read data blocks
+    let start = cursor.position;
+    let data_count = cursor.read_u8()?;
+    let mut header_buf = [0; 4];
+    for _ in 0..data_count {
+        let offset = cursor.position;
+        cursor.read_exact(&mut header_buf)?;
+        match &header_buf {
+            b"MAIN" => {
+                // Read the tiles
+                let block_framing = read_helper::read_compressed(&mut *cursor, limit, scratch)?
+                    .ok_or(too_large(offset))?;
+                if block_framing == Framing::RawDeflate {
+                    framing = block_framing;
+                }
+                on_raw_block(BlockKind::Main, &scratch.encoded);
+                let raw_tiles = &scratch.decoded;
+                // This is checked before anything is allocated,
+                // so a huge size in a small file can't allocate its whole area
+                if raw_tiles.len() as u64 != u64::from(layer.width) * u64::from(layer.height) * 2 {
+                    return Err(ReadError::InvalidLayerLength { offset });
+                }
+                // We cannot do reinterpretation here,
+                // since Tile.id has an alignment of 2,
+                // while the vector has an alignment of 1,
+                // and the file's byte order may not match the platform's.
+                let mut tiles = Vec::with_capacity(raw_tiles.len() / 2);
+                tiles.extend(
+                    raw_tiles
+                        .chunks_exact(2)
+                        .map(|chunk| Tile::from_file_bytes([chunk[0], chunk[1]])),
+                );
+                layer.data = tiles;
+            }
+            b"DATA" => {
+                let cell_size = cursor.read_u8()?.min(4);
+                let mut default_value = [0; 4];
+                cursor.read_exact(&mut default_value)?;
+                let index = data_index;
+                data_index += 1;
+                if options.skip_sublayers {
+                    read_helper::read_encoded(&mut *cursor, scratch)?;
+                    on_raw_block(BlockKind::Data(index), &scratch.encoded);
+                    continue;
+                }
+                let block_framing = read_helper::read_compressed(&mut *cursor, limit, scratch)?
+                    .ok_or(too_large(offset))?;
+                if block_framing == Framing::RawDeflate {
+                    framing = block_framing;
+                }
+                on_raw_block(BlockKind::Data(index), &scratch.encoded);
+                // The data is checked against the layer's size before anything is allocated,
+                // so a huge size in a small file can't allocate its whole area
+                layer
+                    .add_sublayer_with_data(
+                        &default_value[..cell_size as usize],
+                        scratch.decoded.clone(),
+                    )
+                    .map_err(|_| ReadError::InvalidLayerLength { offset })?;
+            }
+            header => {
+                let header = String::from_utf8_lossy(header).into_owned();
+                return Err(ReadError::InvalidHeader { header, offset });
+            }
+        }
+    }
+    // A layer without a `MAIN` block has no tiles, which only fits if it has no area
+    if layer.data.len() as u64 != u64::from(layer.width) * u64::from(layer.height) {
+        return Err(ReadError::InvalidLayerLength { offset: start });
+    }
+    Ok(framing)
+}
+
+/// Seek past the data blocks of a layer, without decoding them.
+fn skip_layer_data<R: Read + Seek>(
+    cursor: &mut read_helper::Counter<R>,
+    len: u64,
+) -> Result<(), ReadError> {
+    let data_count = cursor.read_u8()?;
+    let mut header_buf = [0; 4];
+    for _ in 0..data_count {
+        let offset = cursor.position;
+        cursor.read_exact(&mut header_buf)?;
+        match &header_buf {
+            b"MAIN" => {}
+            // Skip the cell size and default value
+            b"DATA" => cursor.skip(5)?,
+            header => {
+                let header = String::from_utf8_lossy(header).into_owned();
+                return Err(ReadError::InvalidHeader { header, offset });
+            }
+        }
+        let compressed_size = cursor.read_u32::<LittleEndian>()?;
+        cursor.skip(compressed_size as u64)?;
+        // Seeking past the end isn't an error, so check for that here
+        if cursor.position > len {
+            return Err(ReadError::IoError(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "compressed data ended before its declared size",
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Which data block of a layer something is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum BlockKind {
+    /// The `MAIN` block, holding the tiles.
+    Main,
+    /// The `DATA` block of the sublayer at this index.
+    Data(usize),
+}
+
+/// The compressed bytes of the data blocks of a tilemap's layers,
+/// keyed by the index of their layer and which block of it they are,
+/// as returned by [`TileMap::read_with_raw_blocks`].
+pub type RawBlocks = HashMap<(usize, BlockKind), Vec<u8>>;
+
+/// A summary of what a tilemap holds, as returned by [`TileMap::stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct TileMapStats {
+    /// How many layers there are.
+    pub layers: usize,
+    /// How many tilesets there are.
+    pub tilesets: usize,
+    /// How many properties there are.
+    pub properties: usize,
+    /// How many tiles there are across all layers.
+    pub tiles: usize,
+    /// How many of those tiles aren't empty.
+    pub non_empty_tiles: usize,
+    /// How many bytes of cell data there are across all sublayers.
+    pub sublayer_bytes: usize,
+    /// The widest width and tallest height among the layers.
+    pub bounds: (u32, u32),
+}
+
+/// What changed between two tilemaps, as found by [`TileMap::diff`].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TileMapDiff {
+    /// The indices of layers in the new tilemap that aren't in the old one.
+    pub added_layers: Vec<usize>,
+    /// The indices of layers in the old tilemap that aren't in the new one.
+    pub removed_layers: Vec<usize>,
+    /// Layers that are in both tilemaps, but differ between them.
+    pub modified_layers: Vec<LayerDiff>,
+    /// Properties that differ between the tilemaps, with their old and new values,
+    /// which are `None` if the property was added or removed.
+    pub properties: HashMap<String, (Option<Property>, Option<Property>)>,
+}
+
+impl TileMapDiff {
+    /// Returns whether no layers or properties changed.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added_layers.is_empty()
+            && self.removed_layers.is_empty()
+            && self.modified_layers.is_empty()
+            && self.properties.is_empty()
+    }
+}
+
+/// How a layer changed between two tilemaps, as part of a [`TileMapDiff`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerDiff {
+    /// The index of the layer in the old tilemap.
+    pub old_index: usize,
+    /// The index of the layer in the new tilemap.
+    pub new_index: usize,
+    /// How the tiles of the layer changed.
+    pub tiles: TileDiff,
+    /// Whether anything but the tiles changed, like the layer's settings or sublayers.
+    pub metadata_changed: bool,
+}
+
+/// How the tiles of a layer changed, as part of a [`LayerDiff`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum TileDiff {
+    /// The layer kept its size, and these tiles changed,
+    /// as their X and Y positions, their old tile, and their new tile.
+    ///
+    /// This is empty if only the metadata of the layer changed.
+    Changed(Vec<(usize, usize, Tile, Tile)>),
+    /// The layer changed size, from the first width and height to the second,
+    /// so its tiles can't be compared one by one.
+    ShapeChanged((u32, u32), (u32, u32)),
+}
+
+impl LayerDiff {
+    /// Compare two layers, which may be the same.
+    fn new((old_index, old): (usize, &Layer), (new_index, new): (usize, &Layer)) -> Self {
+        let tiles = if (old.width, old.height) == (new.width, new.height) {
+            let width = old.width as usize;
+            let changed = old
+                .data
+                .iter()
+                .zip(&new.data)
+                .enumerate()
+                .filter(|(_, (a, b))| a != b)
+                .map(|(index, (&a, &b))| (index % width, index / width, a, b))
+                .collect();
+            TileDiff::Changed(changed)
+        } else {
+            TileDiff::ShapeChanged((old.width, old.height), (new.width, new.height))
+        };
+        // Compare the settings by comparing copies without any tiles or sublayers
+        let metadata_changed =
+            old.empty_like() != new.empty_like() || old.sublayers != new.sublayers;
+        LayerDiff {
+            old_index,
+            new_index,
+            tiles,
+            metadata_changed,
+        }
+    }
+}
+
+/// Find the longest sequence of layers that both slices share in order,
+/// returning the index pairs of the matched layers.
+fn match_layers(old: &[Layer], new: &[Layer]) -> Vec<(usize, usize)> {
+    let stride = new.len() + 1;
+    // The length of the longest shared sequence between old[i..] and new[j..],
+    // at i * stride + j
+    let mut lengths = vec![0_usize; (old.len() + 1) * stride];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i * stride + j] = if old[i] == new[j] {
+                lengths[(i + 1) * stride + j + 1] + 1
+            } else {
+                lengths[(i + 1) * stride + j].max(lengths[i * stride + j + 1])
+            };
+        }
+    }
+    let mut matches = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            matches.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * stride + j] >= lengths[i * stride + j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    matches
+}
+
+/// Everything but the tiles and sublayers of a tilemap,
+/// as read by [`TileMap::read_header_only`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TileMapHeader {
+    /// The file version of the tilemap.
+    pub version: u16,
+    /// The byte stored alongside the version.
+    pub version_flags: u8,
+    /// The metadata of each layer of the tilemap.
+    pub layers: Vec<LayerHeader>,
+    /// The tilesets of the tilemap.
+    pub tilesets: Vec<TileSet>,
+    /// The properties of the tilemap.
+    pub properties: HashMap<String, Property>,
+    /// Blocks that were not recognized, in the order they were read.
+    pub unknown_blocks: Vec<([u8; 4], Vec<u8>)>,
+    /// Where the tilemap started in the stream.
+    start: u64,
+}
+
+impl TileMapHeader {
+    /// Load a layer, with its tiles and sublayers, from the stream this header was read from.
+    ///
+    /// # Errors
+    /// Errors if the layer's data fails to be read.
+    ///
+    /// # Panics
+    /// Panics if there is no layer at the index.
+    pub fn load_layer<R: Read + Seek>(
+        &self,
+        mut cursor: R,
+        index: usize,
+    ) -> Result<Layer, ReadError> {
+        let header = &self.layers[index];
+        cursor.seek(SeekFrom::Start(self.start + header.data_offset))?;
+        // Keep offsets relative to the start of the tilemap, like when reading it all at once
+        let mut cursor = read_helper::Counter::at(cursor, header.data_offset);
+        let mut layer = header.to_layer();
+        read_layer_data(
+            &mut cursor,
+            &mut layer,
+            &ReadOptions::default(),
+            &mut ReadScratch::new(),
+            |_, _| {},
+        )?;
+        Ok(layer)
+    }
+}
+
+/// A seekable readable holding a tilemap, with its [header](TileMapHeader) already read,
+/// that loads layers from it on demand.
+///
+/// Nothing is cached, so each layer is read again every time it's asked for.
+#[derive(Debug)]
+pub struct TileMapReader<R> {
+    reader: R,
+    header: TileMapHeader,
+}
+
+impl<R: Read + Seek> TileMapReader<R> {
+    /// Read the header of a tilemap from a seekable readable, like [`TileMap::read_header_only`].
+    ///
+    /// # Errors
+    /// Errors if the file fails to be read.
+    pub fn new(mut reader: R) -> Result<Self, ReadError> {
+        let header = TileMap::read_header_only(&mut reader)?;
+        Ok(TileMapReader { reader, header })
+    }
+
+    /// Returns everything but the tiles and sublayers of the tilemap.
+    #[inline]
+    #[must_use]
+    pub fn header(&self) -> &TileMapHeader {
+        &self.header
+    }
+
+    /// Returns how many layers the tilemap has.
+    #[inline]
+    #[must_use]
+    pub fn layer_count(&self) -> usize {
+        self.header.layers.len()
+    }
+
+    /// Returns the metadata of the layer at the index, if there is one.
+    #[inline]
+    #[must_use]
+    pub fn layer_meta(&self, index: usize) -> Option<&LayerHeader> {
+        self.header.layers.get(index)
+    }
+
+    /// Load the layer at the index, with its tiles and sublayers, like [`TileMapHeader::load_layer`].
+    ///
+    /// # Errors
+    /// Errors if the layer's data fails to be read.
+    ///
+    /// # Panics
+    /// Panics if there is no layer at the index.
+    pub fn read_layer(&mut self, index: usize) -> Result<Layer, ReadError> {
+        self.header.load_layer(&mut self.reader, index)
+    }
+
+    /// Returns the readable, giving up the header.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+/// The metadata of a layer, without its tiles or sublayers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerHeader {
+    /// Width of this layer.
+    pub width: u32,
+    /// Height of this layer.
+    pub height: u32,
+    /// Index of the tileset of this layer.
+    pub tileset: u8,
+    /// Index of the collision of this layer.
+    pub collision: u8,
+    /// The XY position offset of this layer.
+    pub offset: (i32, i32),
+    /// The XY scroll of this layer.
+    pub scroll: (f32, f32),
+    /// Which axes among XY this layer wraps on.
+    pub wrap: (bool, bool),
+    /// Whether the layer is visible.
+    pub visible: bool,
+    /// Opacity of this layer.
+    pub opacity: f32,
+    /// Dimensions of the tiles in this layer.
+    pub tile_dimensions: (u16, u16),
+    /// The sublayer link of this layer.
+    pub sublayer_link: SubLayerLink,
+    /// Where the data blocks of this layer start, relative to the start of the tilemap.
+    data_offset: u64,
+}
+
+impl LayerHeader {
+    /// Make a layer with this metadata, and no data yet.
+    fn to_layer(&self) -> Layer {
+        Layer {
+            width: self.width,
+            height: self.height,
+            tileset: self.tileset,
+            collision: self.collision,
+            offset: self.offset,
+            scroll: self.scroll,
+            wrap: self.wrap,
+            visible: self.visible,
+            opacity: self.opacity,
+            tile_dimensions: self.tile_dimensions,
+            sublayer_link: self.sublayer_link.clone(),
+            ..Layer::default()
+        }
+    }
+}
+
+/// A single layer of a tilemap.
+///
+/// Layers compare and hash their floating point fields by their bits,
+/// so a `NaN` opacity equals itself, but `0.0` and `-0.0` scrolls differ.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Layer {
+    pub(crate) data: Vec<Tile>,
+    /// Width of this layer.
+    pub(crate) width: u32,
+    /// Height of this layer.
+    pub(crate) height: u32,
+    /// Index of the tileset of this layer.
+    pub tileset: u8,
+    /// Index of the collision of this layer.
+    pub collision: u8,
+    /// The XY position offset of this layer.
+    pub offset: (i32, i32),
+    /// The XY scroll of this layer.
+    pub scroll: (f32, f32),
+    /// Which axes among XY this layer wraps on.
+    pub wrap: (bool, bool),
+    /// Whether the layer is visible.
+    pub visible: bool,
+    /// Opacity of this layer.
+    pub opacity: f32,
+    /// Dimensions of the tiles in this layer.
+    pub tile_dimensions: (u16, u16),
+    /// The sublayers of this layer.
+    /// Any more than 254 sublayers will not be saved,
+    /// as the count is stored in a byte alongside the tiles.
+    pub sublayers: Vec<SubLayer>,
+    /// The sublayer link of this layer.
+    pub sublayer_link: SubLayerLink,
+    /// The tile that resizing and clearing this layer fills tiles with,
+    /// which is the tile default (`0xFFFF`) unless changed.
+    ///
+    /// This isn't stored in files, so layers that are read always start with the tile default.
+    pub default_tile: Tile,
+}
+
+impl Layer {
+    /// Every field of the layer, with the floats swapped for their bits.
+    #[allow(clippy::type_complexity)]
+    fn key(
+        &self,
+    ) -> (
+        (&[Tile], u32, u32, u8, u8, (i32, i32)),
+        ((u32, u32), (bool, bool), bool, u32, (u16, u16)),
+        (&[SubLayer], &SubLayerLink, Tile),
+    ) {
+        (
+            (
+                &self.data,
+                self.width,
+                self.height,
+                self.tileset,
+                self.collision,
+                self.offset,
+            ),
+            (
+                (self.scroll.0.to_bits(), self.scroll.1.to_bits()),
+                self.wrap,
+                self.visible,
+                self.opacity.to_bits(),
+                self.tile_dimensions,
+            ),
+            (&self.sublayers, &self.sublayer_link, self.default_tile),
+        )
+    }
+}
+
+impl PartialEq for Layer {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Layer {}
+
+impl Hash for Layer {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
+
+impl IntoIterator for Layer {
+    type Item = Tile;
+    type IntoIter = alloc::vec::IntoIter<Tile>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Layer {
+    type Item = &'a Tile;
+    type IntoIter = core::slice::Iter<'a, Tile>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut Layer {
+    type Item = &'a mut Tile;
+    type IntoIter = core::slice::IterMut<'a, Tile>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.iter_mut()
+    }
+}
+
+impl Default for Layer {
+    fn default() -> Self {
+        Layer {
+            data: Vec::new(),
+            width: 0,
+            height: 0,
+            tileset: 0,
+            collision: 0,
+            offset: (0, 0),
+            scroll: (0.0, 0.0),
+            wrap: (false, false),
+            visible: true,
+            opacity: 1.0,
+            tile_dimensions: (16, 16),
+            sublayer_link: SubLayerLink::default(),
+            sublayers: Vec::new(),
+            default_tile: Tile::default(),
+        }
+    }
+}
+
+impl Layer {
+    /// Resize the layer, filling empty tiles with the layer's [`default_tile`](Layer::default_tile).
+    ///
+    /// The top left corner stays in place.
+    /// If the width is changed, this will reallocate the data buffer!
+    ///
+    /// # Panics
+    /// Panics if the resulting area overflows a u32.
+    /// Use [`Layer::try_resize`] for sizes that aren't known to fit,
+    /// like those from untrusted input.
+    #[inline]
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.resize_anchored(width, height, Anchor::TopLeft);
+    }
+
+    /// Resize the layer like [`Layer::resize`], leaving it as it was if the resulting area overflows a u32.
+    ///
+    /// # Errors
+    /// Errors if the resulting area overflows a u32.
+    pub fn try_resize(&mut self, width: u32, height: u32) -> Result<(), AreaOverflow> {
+        AreaOverflow::check(width, height)?;
+        self.resize(width, height);
+        Ok(())
+    }
+
+    /// Resize the layer, filling empty tiles with the given tile.
+    ///
+    /// The top left corner stays in place, and sublayer cells are filled with their defaults.
+    /// If the width is changed, this will reallocate the data buffer!
+    ///
+    /// # Panics
+    /// Panics if the resulting area overflows a u32.
+    /// Check the size with [`Layer::try_resize`] first if it isn't known to fit.
+    #[inline]
+    pub fn resize_with(&mut self, width: u32, height: u32, fill: Tile) {
+        self.resize_anchored_with(width, height, Anchor::TopLeft, fill);
+    }
+
+    /// Resize the layer and its sublayers, keeping the given anchor of the layer in place
+    /// and filling empty tiles with their defaults.
+    ///
+    /// Unless the anchor is [`Anchor::TopLeft`], this always reallocates the data buffer.
+    ///
+    /// # Panics
+    /// Panics if the resulting area overflows a u32.
+    /// Check the size with [`Layer::try_resize`] first if it isn't known to fit.
+    #[inline]
+    pub fn resize_anchored(&mut self, width: u32, height: u32, anchor: Anchor) {
+        self.resize_anchored_with(width, height, anchor, self.default_tile);
+    }
+
+    /// Resize the layer and its sublayers, keeping the given anchor of the layer in place
+    /// and filling empty tiles with the given tile.
+    fn resize_anchored_with(&mut self, width: u32, height: u32, anchor: Anchor, fill: Tile) {
+        assert!(
+            width.checked_mul(height).is_some(),
+            "layer area overflows a u32"
+        );
+        if anchor == Anchor::TopLeft || width == 0 || height == 0 {
+            self.resize_top_left(width, height, fill);
+            return;
+        }
+        self.data = grid::resize_anchored(
+            &self.data,
+            (self.width as usize, self.height as usize),
+            (width as usize, height as usize),
+            anchor.factors(),
+            &[fill],
+        );
+        self.width = width;
+        self.height = height;
+        for sublayer in &mut self.sublayers {
+            sublayer.resize_anchored(width, height, anchor);
+        }
+    }
+
+    /// Resize the layer, keeping the top left corner in place,
+    /// and reallocating as little as possible.
+    fn resize_top_left(&mut self, width: u32, height: u32, fill: Tile) {
+        if (self.width == width && self.height == height)
+            || ((self.width == 0 || self.height == 0) && (width == 0 || height == 0))
+        {
+            // This does nothing!
+            return;
+        }
+        if width == 0 || height == 0 {
+            // Clear
+            self.width = 0;
+            self.height = 0;
+            self.data.clear();
+            for sublayer in &mut self.sublayers {
+                sublayer.resize(width, height);
+            }
+            return;
+        }
+        if self.width == 0 || self.height == 0 {
+            // Construct
+            self.width = width;
+            self.height = height;
+            self.data = iter::repeat_n(fill, width as usize * height as usize).collect();
+            for sublayer in &mut self.sublayers {
+                sublayer.resize(width, height);
+            }
+            return;
+        }
+        if self.height > height {
+            // Remove rows
+            self.data.truncate(self.width as usize * height as usize);
+        } else if self.height < height {
+            // Add rows
+            self.data.extend(iter::repeat_n(
+                fill,
+                self.width as usize * (height - self.height) as usize,
+            ));
+        }
+        if self.width != width {
+            let chunks = self.data.chunks(self.width as usize);
+            self.data = if self.width < width {
+                // Old less than new, add elements
+                chunks
+                    .flat_map(|chunk| {
+                        chunk
+                            .iter()
+                            .copied()
+                            .chain(iter::repeat_n(fill, (width - self.width) as usize))
+                    })
+                    .collect()
+            } else {
+                // Truncate elements
+                chunks
+                    .flat_map(|chunk| chunk.iter().copied().take(width as usize))
+                    .collect()
+            };
         }
-        if !self.tilesets.is_empty() {
-            let mut cur = Header::new(&mut cursor, b"TILE");
-            let len = self.tilesets.len().min(255) as u8;
-            cur.write_u8(len)?;
-            for tileset in self.tilesets.iter().take(0xFF) {
-                cur.write_u8(0)?; // Padding
-                cur.write_u8(tileset.transparent_color.2)?; // B
-                cur.write_u8(tileset.transparent_color.1)?; // G
-                cur.write_u8(tileset.transparent_color.0)?; // R
-                write_helper::write_short_string(&mut cur, &tileset.path)?;
-            }
-            cur.write_header()?;
+        self.width = width;
+        self.height = height;
+        for sublayer in &mut self.sublayers {
+            sublayer.resize(width, height);
         }
-        if !self.layers.is_empty() {
-            let mut cur = Header::new(&mut cursor, b"LAYR");
-            // Can only store up to 65535 layers
-            cur.write_u16::<LittleEndian>(self.layers.len().min(u16::MAX as usize) as u16)?;
-            for layer in self.layers.iter().take(0xFFFF) {
-                cur.write_u32::<LittleEndian>(layer.width)?;
-                cur.write_u32::<LittleEndian>(layer.height)?;
-                // Write layer settings
-                cur.write_u16::<LittleEndian>(layer.tile_dimensions.0)?;
-                cur.write_u16::<LittleEndian>(layer.tile_dimensions.1)?;
-                cur.write_u8(layer.tileset)?;
-                cur.write_u8(layer.collision)?;
-                cur.write_i32::<LittleEndian>(layer.offset.0)?;
-                cur.write_i32::<LittleEndian>(layer.offset.1)?;
-                cur.write_f32::<LittleEndian>(layer.scroll.0)?;
-                cur.write_f32::<LittleEndian>(layer.scroll.1)?;
-                cur.write_u8(layer.wrap.0 as u8)?;
-                cur.write_u8(layer.wrap.1 as u8)?;
-                cur.write_u8(layer.visible as u8)?;
-                cur.write_f32::<LittleEndian>(layer.opacity)?;
-                // Write sublayer link
-                cur.write_u8(layer.sublayer_link.tileset)?;
-                cur.write_u8(layer.sublayer_link.animation)?;
-                cur.write_u8(layer.sublayer_link.animation_frame)?;
-                if layer.width.min(layer.height) == 0 {
-                    // Empty layer
-                    cur.write_u8(0)?; // Layer size
-                    continue;
-                }
-                // Number of headers in this section
-                // Add one for the main header
-                cur.write_u8((layer.sublayers.len() + 1).min(255) as u8)?;
-                cur.write_all(b"MAIN")?;
-                // Use bytemuck to safely cast the tiles
-                let raw_tiles = layer.data.as_slice();
-                let byte_slice: &[u8] = cast_slice(raw_tiles);
-                write_helper::write_compressed(&mut cur, byte_slice)?;
-                for sublayer in layer.sublayers.iter().take(255) {
-                    cur.write_all(b"DATA")?;
-                    cur.write_u8(sublayer.cell_size)?;
-                    cur.write_all(&sublayer.default_value)?;
-                    write_helper::write_compressed(&mut cur, sublayer.data.as_slice())?;
-                }
+    }
+
+    /// Add a new sublayer to the layer, returning a mutable reference to it.
+    pub fn add_sublayer(&mut self, default_value: &[u8]) -> &mut SubLayer {
+        self.sublayers
+            .push(SubLayer::with_size(default_value, self.width, self.height));
+        // SAFETY: we literally just pushed to this
+        unsafe { self.sublayers.last_mut().unwrap_unchecked() }
+    }
+
+    /// Add a new sublayer to the layer with the given cell data,
+    /// returning a mutable reference to it.
+    ///
+    /// The data is used as-is, laid out row by row like [`SubLayer::data`].
+    ///
+    /// # Errors
+    /// Errors if the default value is longer than 4 bytes,
+    /// or if the data isn't as long as the layer's area times the length of the default value.
+    pub fn add_sublayer_with_data(
+        &mut self,
+        default_value: &[u8],
+        data: Vec<u8>,
+    ) -> Result<&mut SubLayer, SubLayerShapeError> {
+        if default_value.len() > 4 {
+            return Err(DefaultTooLong {
+                len: default_value.len(),
             }
-            cur.write_header()?;
+            .into());
+        }
+        let expected = (self.width as usize)
+            .checked_mul(self.height as usize)
+            .and_then(|area| area.checked_mul(default_value.len()));
+        if expected != Some(data.len()) {
+            return Err(SubLayerShapeError::WrongLength {
+                expected: expected.unwrap_or(usize::MAX),
+                actual: data.len(),
+            });
+        }
+        // Start from an empty sublayer, so the default data is never allocated
+        let mut sublayer = SubLayer::with_size(default_value, 0, 0);
+        (sublayer.width, sublayer.height, sublayer.data) = (self.width, self.height, data);
+        self.sublayers.push(sublayer);
+        // SAFETY: we just pushed to this, so it isn't empty
+        Ok(unsafe { self.sublayers.last_mut().unwrap_unchecked() })
+    }
+
+    /// Returns the width of the layer.
+    #[inline]
+    #[must_use]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the height of the layer.
+    #[inline]
+    #[must_use]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the size of the layer in pixels, going by its [`tile_dimensions`](Layer::tile_dimensions).
+    #[inline]
+    #[must_use]
+    pub fn pixel_size(&self) -> (u64, u64) {
+        (
+            self.width as u64 * self.tile_dimensions.0 as u64,
+            self.height as u64 * self.tile_dimensions.1 as u64,
+        )
+    }
+
+    /// Returns the area the layer covers in pixels, as its [`offset`](Layer::offset)
+    /// followed by its [pixel size](Layer::pixel_size).
+    #[inline]
+    #[must_use]
+    pub fn pixel_rect(&self) -> (i64, i64, u64, u64) {
+        let (w, h) = self.pixel_size();
+        (self.offset.0 as i64, self.offset.1 as i64, w, h)
+    }
+
+    /// Returns whether the layer has no tiles, because its width or height is 0.
+    ///
+    /// Empty layers are written without any tile or sublayer data.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    /// Get a tile by position.
+    /// Returns None if out of bounds
+    #[must_use]
+    pub fn get(&self, position: (usize, usize)) -> Option<&Tile> {
+        self.data.get(self.index_of(position)?)
+    }
+
+    /// Get a tile by position, mutably.
+    /// Returns None if out of bounds
+    pub fn get_mut(&mut self, position: (usize, usize)) -> Option<&mut Tile> {
+        let index = self.index_of(position)?;
+        self.data.get_mut(index)
+    }
+
+    /// Find where a position is in the data,
+    /// returning `None` if it's out of bounds or the index would overflow.
+    ///
+    /// This doesn't check the height, as indexing the data does that.
+    fn index_of(&self, (x, y): (usize, usize)) -> Option<usize> {
+        if x >= self.width as usize {
+            return None;
+        }
+        y.checked_mul(self.width as usize)?.checked_add(x)
+    }
+
+    /// Find where a position is in the data, panicking if it's out of bounds.
+    fn expect_index(&self, (x, y): (usize, usize)) -> usize {
+        match self.index_of((x, y)) {
+            Some(index) if index < self.data.len() => index,
+            _ => panic!(
+                "position ({x}, {y}) is out of bounds of the {}x{} layer",
+                self.width, self.height
+            ),
         }
+    }
+
+    /// Get a tile by position, taking the same coordinates as [`SubLayer::get`].
+    /// Returns None if out of bounds.
+    #[inline]
+    #[must_use]
+    pub fn tile(&self, (x, y): (u32, u32)) -> Option<&Tile> {
+        self.get((x as usize, y as usize))
+    }
+
+    /// Set a tile by position, without panicking.
+    ///
+    /// # Errors
+    /// Errors if the position is out of bounds.
+    pub fn set(&mut self, (x, y): (u32, u32), tile: Tile) -> Result<(), OutOfBounds> {
+        let (width, height) = (self.width, self.height);
+        let Some(slot) = self.tile_mut((x, y)) else {
+            return Err(OutOfBounds {
+                x,
+                y,
+                width,
+                height,
+            });
+        };
+        *slot = tile;
         Ok(())
     }
 
+    /// Get a tile by position, mutably,
+    /// taking the same coordinates as [`SubLayer::get_mut`].
+    /// Returns None if out of bounds.
+    #[inline]
+    pub fn tile_mut(&mut self, (x, y): (u32, u32)) -> Option<&mut Tile> {
+        self.get_mut((x as usize, y as usize))
+    }
+
     /// Constructs a new instance from the default.
     #[inline]
     #[must_use]
     pub fn new() -> Self {
         Self::default()
     }
-}
 
-/// A single layer of a tilemap.
-#[derive(Clone, PartialEq)]
-pub struct Layer {
-    pub(crate) data: Vec<Tile>,
-    /// Width of this layer.
-    pub(crate) width: u32,
-    /// Height of this layer.
-    pub(crate) height: u32,
-    /// Index of the tileset of this layer.
-    pub tileset: u8,
-    /// Index of the collision of this layer.
-    pub collision: u8,
-    /// The XY position offset of this layer.
-    pub offset: (i32, i32),
-    /// The XY scroll of this layer.
-    pub scroll: (f32, f32),
-    /// Which axes among XY this layer wraps on.
-    pub wrap: (bool, bool),
-    /// Whether the layer is visible.
-    pub visible: bool,
-    /// Opacity of this layer.
-    pub opacity: f32,
-    /// Dimensions of the tiles in this layer.
-    pub tile_dimensions: (u16, u16),
-    /// The sublayers of this layer.
-    /// Any more than 255 sublayers will not be saved.
-    pub sublayers: Vec<SubLayer>,
-    /// The sublayer link of this layer.
-    pub sublayer_link: SubLayerLink,
-}
+    /// Constructs a [`LayerBuilder`] for the default layer.
+    #[inline]
+    pub fn builder() -> LayerBuilder {
+        LayerBuilder::new()
+    }
+
+    /// Returns the tiles of the layer in row-major order,
+    /// which is `width * height` tiles long if the layer is [consistent](Layer::is_consistent).
+    #[inline]
+    #[must_use]
+    pub fn tiles(&self) -> &[Tile] {
+        &self.data
+    }
+
+    /// Returns the tiles of the layer in row-major order, mutably,
+    /// which is `width * height` tiles long if the layer is [consistent](Layer::is_consistent).
+    #[inline]
+    pub fn tiles_mut(&mut self) -> &mut [Tile] {
+        &mut self.data
+    }
+
+    /// Returns a row of tiles of the layer, or `None` if it's out of bounds.
+    #[must_use]
+    pub fn row(&self, y: u32) -> Option<&[Tile]> {
+        if y >= self.height {
+            return None;
+        }
+        let width = self.width as usize;
+        let start = (y as usize).checked_mul(width)?;
+        self.data.get(start..start.checked_add(width)?)
+    }
+
+    /// Creates an iterator over each tile of the layer, returning a reference.
+    pub fn iter(&self) -> impl Iterator<Item = &Tile> {
+        self.data.iter()
+    }
+
+    /// Creates an iterator over each tile of the layer, returning a mutable reference.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Tile> {
+        self.data.iter_mut()
+    }
+
+    /// Creates an iterator over each tile of the layer in row-major order,
+    /// returning its position and a reference.
+    pub fn iter_coords(&self) -> impl Iterator<Item = ((usize, usize), &Tile)> {
+        let width = self.width as usize;
+        // The closure is never called for zero-sized layers, as they hold no data
+        self.data
+            .iter()
+            .enumerate()
+            .map(move |(i, tile)| ((i % width, i / width), tile))
+    }
+
+    /// Creates an iterator over each tile of the layer in row-major order,
+    /// returning its position and a mutable reference.
+    pub fn iter_coords_mut(&mut self) -> impl Iterator<Item = ((usize, usize), &mut Tile)> {
+        let width = self.width as usize;
+        self.data
+            .iter_mut()
+            .enumerate()
+            .map(move |(i, tile)| ((i % width, i / width), tile))
+    }
+
+    /// Collect the IDs of the tiles into a grid of rows.
+    #[must_use]
+    pub fn to_id_grid(&self) -> Vec<Vec<u16>> {
+        if self.width == 0 {
+            return vec![Vec::new(); self.height as usize];
+        }
+        self.data
+            .chunks(self.width as usize)
+            .map(|row| row.iter().map(Tile::id).collect())
+            .collect()
+    }
+
+    /// Construct a layer from a grid of rows of tile IDs, with the default settings.
+    ///
+    /// # Errors
+    /// Errors if the rows aren't all the same length.
+    ///
+    /// # Panics
+    /// Panics if the grid is more than `u32::MAX` tiles wide or tall.
+    pub fn from_id_grid(grid: &[Vec<u16>]) -> Result<Layer, RaggedGrid> {
+        let width = grid.first().map_or(0, Vec::len);
+        if let Some((row, ragged)) = grid.iter().enumerate().find(|(_, row)| row.len() != width) {
+            return Err(RaggedGrid {
+                row,
+                expected: width,
+                actual: ragged.len(),
+            });
+        }
+        let mut layer = Layer::new();
+        layer.data = grid.iter().flatten().copied().map(Tile::from_id).collect();
+        layer.width = u32::try_from(width).expect("grid should be at most u32::MAX tiles wide");
+        layer.height =
+            u32::try_from(grid.len()).expect("grid should be at most u32::MAX tiles tall");
+        Ok(layer)
+    }
+
+    /// Construct a layer of the given size from its tiles, in row-major order.
+    ///
+    /// At most one more tile than the layer holds is taken from the iterator,
+    /// so it can be endless.
+    ///
+    /// # Errors
+    /// Errors if the area of the layer overflows a u32,
+    /// or if there aren't exactly `width * height` tiles.
+    pub fn from_tiles(
+        width: u32,
+        height: u32,
+        tiles: impl IntoIterator<Item = Tile>,
+    ) -> Result<Layer, LayerShapeError> {
+        AreaOverflow::check(width, height)?;
+        let expected = width as usize * height as usize;
+        let tiles = tiles.into_iter();
+        // Trust the iterator's lower bound over the size, so a huge size can't allocate up front
+        let mut data = Vec::with_capacity(tiles.size_hint().0.min(expected));
+        data.extend(tiles.take(expected.saturating_add(1)));
+        if data.len() != expected {
+            return Err(LayerShapeError::WrongLength {
+                expected,
+                actual: data.len(),
+            });
+        }
+        Ok(Layer {
+            data,
+            width,
+            height,
+            ..Layer::default()
+        })
+    }
+
+    /// Mirror the layer and its sublayers from left to right.
+    ///
+    /// Only the layout of the grid changes, the tiles themselves are untouched.
+    pub fn flip_horizontal(&mut self) {
+        grid::flip_horizontal(&mut self.data, self.width as usize, 1);
+        for sublayer in &mut self.sublayers {
+            sublayer.flip_horizontal();
+        }
+    }
+
+    /// Mirror the layer and its sublayers from top to bottom.
+    ///
+    /// Only the layout of the grid changes, the tiles themselves are untouched.
+    pub fn flip_vertical(&mut self) {
+        grid::flip_vertical(&mut self.data, self.width as usize, 1);
+        for sublayer in &mut self.sublayers {
+            sublayer.flip_vertical();
+        }
+    }
+
+    /// Move every tile of the layer and its sublayers by `dx` tiles to the right and `dy` tiles down.
+    ///
+    /// With `wrap`, tiles that move off one edge come back in on the opposite edge.
+    /// Otherwise they're dropped, and the tiles left behind are set to
+    /// [`default_tile`](Layer::default_tile), or the default value of each sublayer.
+    pub fn shift(&mut self, dx: i32, dy: i32, wrap: bool) {
+        let size = (self.width as usize, self.height as usize);
+        let delta = (dx as i64, dy as i64);
+        self.data = grid::shift(&self.data, size, delta, wrap, &[self.default_tile]);
+        for sublayer in &mut self.sublayers {
+            sublayer.shift(dx, dy, wrap);
+        }
+    }
+
+    /// Copy a `w` by `h` region of the layer starting at `(x, y)` into a new layer,
+    /// along with the same region of each sublayer.
+    ///
+    /// All settings of the layer are carried over.
+    /// Returns None if the region is out of bounds.
+    #[must_use]
+    pub fn crop(&self, x: u32, y: u32, w: u32, h: u32) -> Option<Layer> {
+        let region = grid::region((self.width, self.height), (x, y, w, h))?;
+        let mut layer = self.empty_like();
+        if w == 0 || h == 0 {
+            return Some(layer);
+        }
+        layer.data = grid::crop(&self.data, self.width as usize, 1, region);
+        layer.width = w;
+        layer.height = h;
+        layer.sublayers = self
+            .sublayers
+            .iter()
+            .map(|sublayer| sublayer.crop(x, y, w, h))
+            .collect::<Option<_>>()?;
+        Some(layer)
+    }
+
+    /// Crop the layer and its sublayers down to the smallest region holding every tile
+    /// that isn't the layer's [`default_tile`](Layer::default_tile),
+    /// returning how far the top left corner of that region was from the old one, in tiles.
+    ///
+    /// A layer with only default tiles becomes 0x0.
+    pub fn trim(&mut self) -> (u32, u32) {
+        let width = self.width as usize;
+        let mut bounds: Option<(usize, usize, usize, usize)> = None;
+        let area = width * self.height as usize;
+        for (index, _) in self.data[..area.min(self.data.len())]
+            .iter()
+            .enumerate()
+            .filter(|(_, tile)| **tile != self.default_tile)
+        {
+            let (x, y) = (index % width, index / width);
+            bounds = Some(match bounds {
+                None => (x, y, x, y),
+                Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+            });
+        }
+        let Some((x0, y0, x1, y1)) = bounds else {
+            self.resize(0, 0);
+            return (0, 0);
+        };
+        // These all came from within the layer, so they fit in u32s
+        let (x, y) = (x0 as u32, y0 as u32);
+        let (w, h) = ((x1 - x0 + 1) as u32, (y1 - y0 + 1) as u32);
+        match self.crop(x, y, w, h) {
+            Some(trimmed) => {
+                *self = trimmed;
+                (x, y)
+            }
+            // Only an inconsistent sublayer can get here, so leave the layer as it was
+            None => (0, 0),
+        }
+    }
+
+    /// Write a `w` by `h` region of tiles starting at `(x, y)` from a row-major slice,
+    /// the inverse of [`Layer::crop`] for the tiles alone.
+    ///
+    /// Sublayers are left as they were.
+    ///
+    /// # Errors
+    /// Errors if the region is out of bounds, or if there aren't exactly `w * h` tiles.
+    pub fn paste(
+        &mut self,
+        x: u32,
+        y: u32,
+        w: u32,
+        h: u32,
+        tiles: &[Tile],
+    ) -> Result<(), PasteError> {
+        let region = grid::region((self.width, self.height), (x, y, w, h)).ok_or(
+            PasteError::OutOfBounds {
+                region: (x, y, w, h),
+                width: self.width,
+                height: self.height,
+            },
+        )?;
+        // The region is in bounds, so its area fits along with the layer's
+        let expected = w as usize * h as usize;
+        if tiles.len() != expected {
+            return Err(PasteError::WrongLength {
+                expected,
+                actual: tiles.len(),
+            });
+        }
+        grid::paste(&mut self.data, self.width as usize, 1, region, tiles);
+        Ok(())
+    }
+
+    /// Set every tile in a `w` by `h` region starting at `(x, y)` to the given tile.
+    ///
+    /// Any part of the region that is out of bounds is ignored.
+    pub fn fill_rect(&mut self, x: u32, y: u32, w: u32, h: u32, tile: Tile) {
+        let x_end = x.saturating_add(w).min(self.width) as usize;
+        let y_end = y.saturating_add(h).min(self.height) as usize;
+        let width = self.width as usize;
+        for row in (y as usize)..y_end {
+            if let Some(cells) = self
+                .data
+                .get_mut(row * width + x as usize..row * width + x_end)
+            {
+                cells.fill(tile);
+            }
+        }
+    }
+
+    /// Copy the tiles of another layer into this one, with its top left corner at `(dest_x, dest_y)`.
+    ///
+    /// Any part of the source that lands out of bounds is ignored.
+    /// If `skip_empty` is set, tiles in the source that are its [`default_tile`](Layer::default_tile)
+    /// are skipped, leaving the tiles under them as they were.
+    ///
+    /// Sublayers are copied along with the tiles, pairing them up by index.
+    /// Pairs with differing cell sizes are skipped.
+    pub fn blit(&mut self, src: &Layer, dest_x: u32, dest_y: u32, skip_empty: bool) {
+        self.blit_skipping(src, dest_x, dest_y, skip_empty.then_some(src.default_tile));
+    }
+
+    /// Copy a layer's tiles onto this one like [`Layer::blit`],
+    /// leaving out the tiles of the source equal to `skip`, if set.
+    fn blit_skipping(&mut self, src: &Layer, dest_x: u32, dest_y: u32, skip: Option<Tile>) {
+        let w = src.width.min(self.width.saturating_sub(dest_x)) as usize;
+        let h = src.height.min(self.height.saturating_sub(dest_y)) as usize;
+        let (dest_x, dest_y) = (dest_x as usize, dest_y as usize);
+        let (src_width, dest_width) = (src.width as usize, self.width as usize);
+        for y in 0..h {
+            for x in 0..w {
+                let src_index = y * src_width + x;
+                let tile = src.data[src_index];
+                if skip == Some(tile) {
+                    continue;
+                }
+                let dest_index = (dest_y + y) * dest_width + dest_x + x;
+                self.data[dest_index] = tile;
+                for (dest, src) in self.sublayers.iter_mut().zip(&src.sublayers) {
+                    if dest.cell_size != src.cell_size {
+                        continue;
+                    }
+                    let size = dest.cell_size as usize;
+                    // Skip sublayers that weren't resized along with their layer
+                    if let (Some(dest), Some(src)) = (
+                        dest.data
+                            .get_mut(dest_index * size..(dest_index + 1) * size),
+                        src.data.get(src_index * size..(src_index + 1) * size),
+                    ) {
+                        dest.copy_from_slice(src);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns whether two layers have the same size and tiles,
+    /// unlike `==` ignoring their sublayers and everything else about them.
+    #[must_use]
+    pub fn same_tiles(&self, other: &Layer) -> bool {
+        (self.width, self.height) == (other.width, other.height) && self.data == other.data
+    }
+
+    /// Returns whether the layer and its sublayers hold exactly as much data as their size says.
+    ///
+    /// Reading and deserializing reject layers whose data doesn't match their size,
+    /// and the methods of layers keep their sublayers in step with them,
+    /// so the only way for a layer to become inconsistent is for a sublayer of the wrong size
+    /// to be pushed onto [`sublayers`](Layer::sublayers), or resized there, directly.
+    #[must_use]
+    pub fn is_consistent(&self) -> bool {
+        self.data.len() == self.width as usize * self.height as usize
+            && self
+                .sublayers
+                .iter()
+                .all(|sublayer| sublayer.is_consistent_with(self.width, self.height))
+    }
+
+    /// Make the layer [consistent](Layer::is_consistent) again,
+    /// truncating or padding its data and the data of its sublayers to match its size.
+    ///
+    /// Missing tiles and cells are filled with their defaults.
+    pub fn repair(&mut self) {
+        let area = self.width as usize * self.height as usize;
+        self.data.resize(area, self.default_tile);
+        for sublayer in &mut self.sublayers {
+            sublayer.repair(self.width, self.height);
+        }
+    }
 
-impl IntoIterator for Layer {
-    type Item = Tile;
-    type IntoIter = std::vec::IntoIter<Tile>;
+    /// Clamp the opacity of the layer into `0.0..=1.0`, and replace non-finite scrolls with `0.0`.
+    ///
+    /// A `NaN` opacity becomes `1.0`, the default.
+    pub fn sanitize(&mut self) {
+        sanitize_floats(&mut self.opacity, &mut self.scroll);
+    }
 
-    fn into_iter(self) -> Self::IntoIter {
-        self.data.into_iter()
+    /// Replace the ID of every tile in the layer with the result of a function.
+    ///
+    /// Empty tiles are passed in as well, as `0xFFFF`,
+    /// so the function should return that unchanged to keep them empty.
+    pub fn remap_ids(&mut self, mut f: impl FnMut(u16) -> u16) {
+        for tile in &mut self.data {
+            *tile = Tile::from_id(f(tile.id()));
+        }
     }
-}
 
-impl Default for Layer {
-    fn default() -> Self {
+    /// Replace the tileset indices of the layer with the result of a function,
+    /// leaving them alone if it returns `None`.
+    ///
+    /// This covers the layer's [`tileset`](Layer::tileset),
+    /// as well as the cells of its linked tileset sublayer if that has one byte per cell.
+    /// Unset indices (`0xFF`) are never passed in.
+    fn remap_tilesets(&mut self, f: impl Fn(u8) -> Option<u8>) {
+        let remap = |index: &mut u8| {
+            if *index != 0xFF {
+                *index = f(*index).unwrap_or(*index);
+            }
+        };
+        remap(&mut self.tileset);
+        let linked = self.sublayer_link.tileset_index();
+        if let Some(sublayer) = linked.and_then(|index| self.sublayers.get_mut(index)) {
+            if sublayer.cell_size == 1 {
+                sublayer.data.iter_mut().for_each(remap);
+            }
+        }
+    }
+
+    /// Count the tiles of the layer that aren't its [`default_tile`](Layer::default_tile),
+    /// which is the tile default (`0xFFFF`) unless changed.
+    #[must_use]
+    pub fn non_empty_count(&self) -> usize {
+        let empty = self.default_tile;
+        self.data.iter().filter(|tile| **tile != empty).count()
+    }
+
+    /// Reset every tile of the layer to its [`default_tile`](Layer::default_tile),
+    /// without reallocating.
+    pub fn clear(&mut self) {
+        self.data.fill(self.default_tile);
+    }
+
+    /// Swap the two bytes of every tile of the layer, in place.
+    ///
+    /// This is for fixing a layer whose tiles were built from IDs in the wrong byte order,
+    /// such as with [`Tile::from_id`] on IDs that were read or written as little endian.
+    /// The X and Y of a tile are the bytes of its ID in big endian order (see [`Tile::view`]),
+    /// so in a layer like that, they come out swapped. Tiles read from files are never like this.
+    ///
+    /// This swaps every tile, including empty ones, and doesn't touch the layer's
+    /// [`default_tile`](Layer::default_tile). Swapping twice leaves the layer as it was.
+    pub fn swap_tile_endianness(&mut self) {
+        for tile in &mut self.data {
+            *tile = Tile::from_id(tile.id().swap_bytes());
+        }
+    }
+
+    /// Returns a zero-sized layer with the same settings as this one.
+    fn empty_like(&self) -> Layer {
         Layer {
             data: Vec::new(),
             width: 0,
             height: 0,
-            tileset: 0,
-            collision: 0,
-            offset: (0, 0),
-            scroll: (0.0, 0.0),
-            wrap: (false, false),
-            visible: true,
-            opacity: 1.0,
-            tile_dimensions: (16, 16),
-            sublayer_link: SubLayerLink::default(),
+            tileset: self.tileset,
+            collision: self.collision,
+            offset: self.offset,
+            scroll: self.scroll,
+            wrap: self.wrap,
+            visible: self.visible,
+            opacity: self.opacity,
+            tile_dimensions: self.tile_dimensions,
             sublayers: Vec::new(),
+            sublayer_link: self.sublayer_link.clone(),
+            default_tile: self.default_tile,
         }
     }
-}
 
-impl Layer {
-    /// Resize the layer, filling empty tiles with the tile default (`0xFFFF`).
+    /// Clone the tiles and settings of the layer, leaving out its sublayers
+    /// and resetting its sublayer link to the default.
     ///
-    /// If the width is changed, this will reallocate the data buffer!
-    pub fn resize(&mut self, width: u32, height: u32) {
-        if (self.width == width && self.height == height)
-            || ((self.width == 0 || self.height == 0) && (width == 0 || height == 0))
-        {
-            // This does nothing!
-            return;
-        }
-        if width == 0 || height == 0 {
-            // Clear
-            self.width = 0;
-            self.height = 0;
-            self.data.clear();
-            for sublayer in &mut self.sublayers {
-                sublayer.resize(width, height);
-            }
-            return;
+    /// This avoids copying the sublayer data when only the tiles are needed.
+    #[must_use]
+    pub fn clone_tiles_only(&self) -> Layer {
+        Layer {
+            data: self.data.clone(),
+            width: self.width,
+            height: self.height,
+            sublayer_link: SubLayerLink::default(),
+            ..self.empty_like()
         }
+    }
+
+    /// Rotate the layer and its sublayers clockwise by 90 degrees.
+    ///
+    /// This swaps the width and height, as well as the tile dimensions,
+    /// and reallocates the data buffer!
+    pub fn rotate_cw(&mut self) {
         if self.width == 0 || self.height == 0 {
-            // Construct
-            self.width = width;
-            self.height = height;
-            self.data = iter::repeat(Tile::default())
-                .take((width * height) as usize)
-                .collect();
-            for sublayer in &mut self.sublayers {
-                sublayer.resize(width, height);
-            }
             return;
         }
-        if self.height > height {
-            // Remove rows
-            self.data.truncate((self.width * height) as usize);
-        } else if self.height < height {
-            // Add rows
-            self.data.extend(
-                iter::repeat(Tile::default()).take((self.width * (height - self.height)) as usize),
-            );
+        self.data = grid::rotate_cw(&self.data, self.width as usize, self.height as usize, 1);
+        (self.width, self.height) = (self.height, self.width);
+        self.tile_dimensions = (self.tile_dimensions.1, self.tile_dimensions.0);
+        for sublayer in &mut self.sublayers {
+            sublayer.rotate_cw();
         }
-        if self.width != width {
-            let chunks = self.data.chunks(self.width as usize);
-            self.data = if self.width < width {
-                // Old less than new, add elements
-                chunks
-                    .flat_map(|chunk| {
-                        chunk.iter().copied().chain(
-                            iter::repeat(Tile::default()).take((width - self.width) as usize),
-                        )
-                    })
-                    .collect()
-            } else {
-                // Truncate elements
-                chunks
-                    .flat_map(|chunk| chunk.iter().copied().take(width as usize))
-                    .collect()
-            };
+    }
+
+    /// Rotate the layer and its sublayers counterclockwise by 90 degrees.
+    ///
+    /// This swaps the width and height, as well as the tile dimensions,
+    /// and reallocates the data buffer!
+    pub fn rotate_ccw(&mut self) {
+        if self.width == 0 || self.height == 0 {
+            return;
         }
-        self.width = width;
-        self.height = height;
+        self.data = grid::rotate_ccw(&self.data, self.width as usize, self.height as usize, 1);
+        (self.width, self.height) = (self.height, self.width);
+        self.tile_dimensions = (self.tile_dimensions.1, self.tile_dimensions.0);
         for sublayer in &mut self.sublayers {
-            sublayer.resize(width, height);
+            sublayer.rotate_ccw();
         }
     }
+}
 
-    /// Add a new sublayer to the layer, returning a mutable reference to it.
-    pub fn add_sublayer(&mut self, default_value: &[u8]) -> &mut SubLayer {
-        let mut sublayer = SubLayer::default();
-        sublayer.set_default(default_value);
-        sublayer.resize(self.width, self.height);
-        self.sublayers.push(sublayer);
-        // SAFETY: we literally just pushed to this
-        unsafe { self.sublayers.last_mut().unwrap_unchecked() }
+/// Index a layer by `(usize, usize)` coordinates.
+///
+/// There is deliberately no `(u32, u32)` impl alongside this one,
+/// as a second impl would stop integer literals like `layer[(0, 0)]` from inferring.
+/// Use [`Layer::tile`] and [`Layer::tile_mut`] for `u32` coordinates.
+impl Index<(usize, usize)> for Layer {
+    type Output = Tile;
+
+    /// Index by position and return a reference.
+    ///
+    /// # Panics
+    /// Panics if index is out of bounds.
+    fn index(&self, position: (usize, usize)) -> &Self::Output {
+        &self.data[self.expect_index(position)]
     }
+}
 
-    /// Returns the width of the layer.
+impl IndexMut<(usize, usize)> for Layer {
+    /// Index by position and return a mutable reference.
+    ///
+    /// # Panics
+    /// Panics if index is out of bounds.
+    fn index_mut(&mut self, position: (usize, usize)) -> &mut Self::Output {
+        let index = self.expect_index(position);
+        &mut self.data[index]
+    }
+}
+
+/// A builder for a [`Layer`], for setting up a non-default layer in one expression.
+///
+/// ```
+/// # use ct_tilemap::LayerBuilder;
+/// let layer = LayerBuilder::new().size(8, 8).tileset(1).opacity(0.5).build();
+/// assert_eq!(layer.width(), 8);
+/// ```
+#[derive(Debug, Clone, Default)]
+#[must_use]
+pub struct LayerBuilder {
+    layer: Layer,
+    width: u32,
+    height: u32,
+}
+
+impl LayerBuilder {
+    /// Constructs a builder for the default layer.
     #[inline]
-    #[must_use]
-    pub fn width(&self) -> u32 {
-        self.width
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    /// Returns the height of the layer.
+    /// Set the width and height of the layer.
     #[inline]
-    #[must_use]
-    pub fn height(&self) -> u32 {
-        self.height
+    pub fn size(mut self, width: u32, height: u32) -> Self {
+        (self.width, self.height) = (width, height);
+        self
     }
 
-    /// Get a tile by position.
-    /// Returns None if out of bounds
-    #[must_use]
-    pub fn get(&self, (x, y): (usize, usize)) -> Option<&Tile> {
-        let index = y * self.width as usize + x;
-        self.data.get(index)
+    /// Set the index of the tileset of the layer.
+    #[inline]
+    pub fn tileset(mut self, tileset: u8) -> Self {
+        self.layer.tileset = tileset;
+        self
     }
 
-    /// Get a tile by position, mutably.
-    /// Returns None if out of bounds
-    pub fn get_mut(&mut self, (x, y): (usize, usize)) -> Option<&mut Tile> {
-        let index = y * self.width as usize + x;
-        self.data.get_mut(index)
+    /// Set the index of the collision of the layer.
+    #[inline]
+    pub fn collision(mut self, collision: u8) -> Self {
+        self.layer.collision = collision;
+        self
     }
 
-    /// Constructs a new instance from the default.
+    /// Set the XY position offset of the layer.
     #[inline]
-    #[must_use]
-    pub fn new() -> Self {
-        Self::default()
+    pub fn offset(mut self, offset: (i32, i32)) -> Self {
+        self.layer.offset = offset;
+        self
     }
 
-    /// Creates an iterator over each tile of the layer, returning a reference.
-    pub fn iter(&self) -> impl Iterator<Item = &Tile> {
-        self.data.iter()
+    /// Set the XY scroll of the layer.
+    #[inline]
+    pub fn scroll(mut self, scroll: (f32, f32)) -> Self {
+        self.layer.scroll = scroll;
+        self
     }
 
-    /// Creates an iterator over each tile of the layer, returning a mutable reference.
-    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Tile> {
-        self.data.iter_mut()
+    /// Set which axes among XY the layer wraps on.
+    #[inline]
+    pub fn wrap(mut self, wrap: (bool, bool)) -> Self {
+        self.layer.wrap = wrap;
+        self
     }
-}
 
-impl Index<(usize, usize)> for Layer {
-    type Output = Tile;
+    /// Set whether the layer is visible.
+    #[inline]
+    pub fn visible(mut self, visible: bool) -> Self {
+        self.layer.visible = visible;
+        self
+    }
 
-    /// Index by position and return a reference.
+    /// Set the opacity of the layer.
+    #[inline]
+    pub fn opacity(mut self, opacity: f32) -> Self {
+        self.layer.opacity = opacity;
+        self
+    }
+
+    /// Set the dimensions of the tiles in the layer.
+    #[inline]
+    pub fn tile_dimensions(mut self, tile_dimensions: (u16, u16)) -> Self {
+        self.layer.tile_dimensions = tile_dimensions;
+        self
+    }
+
+    /// Set the tile the layer is filled with when it's built, resized, or cleared.
+    #[inline]
+    pub fn default_tile(mut self, default_tile: Tile) -> Self {
+        self.layer.default_tile = default_tile;
+        self
+    }
+
+    /// Build the layer, allocating its data filled with its default tile.
     ///
     /// # Panics
-    /// Panics if index is out of bounds.
-    fn index(&self, (x, y): (usize, usize)) -> &Self::Output {
-        let index = y * self.width as usize + x;
-        &self.data[index]
+    /// Panics if the area of the layer overflows a u32.
+    /// Use [`LayerBuilder::try_build`] for sizes that aren't known to fit,
+    /// like those from untrusted input.
+    #[must_use]
+    pub fn build(self) -> Layer {
+        match self.try_build() {
+            Ok(layer) => layer,
+            Err(err) => panic!("{err}"),
+        }
     }
-}
 
-impl IndexMut<(usize, usize)> for Layer {
-    /// Index by position and return a mutable reference.
+    /// Build the layer like [`LayerBuilder::build`], erroring if its area overflows a u32.
     ///
-    /// # Panics
-    /// Panics if index is out of bounds.
-    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut Self::Output {
-        let index = y * self.width as usize + x;
-        &mut self.data[index]
+    /// # Errors
+    /// Errors if the area of the layer overflows a u32.
+    pub fn try_build(self) -> Result<Layer, AreaOverflow> {
+        let mut layer = self.layer;
+        layer.try_resize(self.width, self.height)?;
+        Ok(layer)
     }
 }
 
 /// A tileset in the image.
-#[derive(Default, Clone, PartialEq, Eq)]
+#[derive(Default, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TileSet {
     /// Path to the tileset image.
     pub path: String,
@@ -670,13 +4163,57 @@ impl TileSet {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Constructs a tileset from a path and a hex color, like `#DA8972` or `DA8972`.
+    ///
+    /// # Errors
+    /// Errors if the color isn't 6 hex digits, optionally after a `#`.
+    pub fn from_hex(path: String, hex: &str) -> Result<TileSet, ParseColorError> {
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        if let Some(c) = digits.chars().find(|c| !c.is_ascii_hexdigit()) {
+            return Err(ParseColorError::InvalidDigit(c));
+        }
+        if digits.len() != 6 {
+            return Err(ParseColorError::InvalidLength(digits.len()));
+        }
+        let mut tileset = TileSet {
+            path,
+            transparent_color: (0, 0, 0),
+        };
+        // The digits were checked above, so this can't fail
+        tileset.set_transparent_rgb(u32::from_str_radix(digits, 16).unwrap_or_default());
+        Ok(tileset)
+    }
+
+    /// Returns the transparent color packed as `0x00RRGGBB`.
+    #[must_use]
+    pub fn transparent_rgb(&self) -> u32 {
+        let (r, g, b) = self.transparent_color;
+        u32::from_be_bytes([0, r, g, b])
+    }
+
+    /// Set the transparent color from one packed as `0x00RRGGBB`.
+    /// The top byte is ignored.
+    pub fn set_transparent_rgb(&mut self, rgb: u32) {
+        let [_, r, g, b] = rgb.to_be_bytes();
+        self.transparent_color = (r, g, b);
+    }
 }
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A value of a property in a layer of a tilemap.
+///
+/// Floats are compared and hashed by their bits,
+/// so `NaN` equals itself, but `0.0` and `-0.0` differ.
 pub enum Property {
     /// Integer.
     Integer(i32),
+    /// Boolean, which is written to files as an integer holding `0` or `1`.
+    ///
+    /// Files can't tell these apart from integers, so they are only read back as booleans
+    /// with [`ReadOptions::coerce_bools`].
+    Boolean(bool),
     /// Floating point.
     Float(f32),
     /// Arbitrary bytes.
@@ -684,12 +4221,122 @@ pub enum Property {
     String(Vec<u8>),
 }
 
+impl PartialEq for Property {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Property::Integer(a), Property::Integer(b)) => a == b,
+            (Property::Boolean(a), Property::Boolean(b)) => a == b,
+            (Property::Float(a), Property::Float(b)) => a.to_bits() == b.to_bits(),
+            (Property::String(a), Property::String(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Property {}
+
+impl Hash for Property {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        core::mem::discriminant(self).hash(state);
+        match self {
+            Property::Integer(i) => i.hash(state),
+            Property::Boolean(b) => b.hash(state),
+            Property::Float(f) => f.to_bits().hash(state),
+            Property::String(s) => s.hash(state),
+        }
+    }
+}
+
+impl Property {
+    /// Returns the integer value, if this is an integer.
+    #[inline]
+    #[must_use]
+    pub fn as_i32(&self) -> Option<i32> {
+        match self {
+            Property::Integer(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// Returns the boolean value, if this is a boolean.
+    #[inline]
+    #[must_use]
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Property::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Returns the floating point value, if this is a float.
+    #[inline]
+    #[must_use]
+    pub fn as_f32(&self) -> Option<f32> {
+        match self {
+            Property::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+
+    /// Returns the raw bytes, if this is a string.
+    #[inline]
+    #[must_use]
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Property::String(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// Returns the string, if this is a string holding valid UTF-8.
+    #[inline]
+    #[must_use]
+    pub fn as_str(&self) -> Option<&str> {
+        self.as_bytes()
+            .and_then(|bytes| core::str::from_utf8(bytes).ok())
+    }
+
+    /// Returns whether this is an integer.
+    #[inline]
+    #[must_use]
+    pub fn is_integer(&self) -> bool {
+        matches!(self, Property::Integer(_))
+    }
+
+    /// Returns whether this is a boolean.
+    #[inline]
+    #[must_use]
+    pub fn is_bool(&self) -> bool {
+        matches!(self, Property::Boolean(_))
+    }
+
+    /// Returns whether this is a float.
+    #[inline]
+    #[must_use]
+    pub fn is_float(&self) -> bool {
+        matches!(self, Property::Float(_))
+    }
+
+    /// Returns whether this is a string.
+    #[inline]
+    #[must_use]
+    pub fn is_string(&self) -> bool {
+        matches!(self, Property::String(_))
+    }
+}
+
 impl From<i32> for Property {
     fn from(value: i32) -> Self {
         Self::Integer(value)
     }
 }
 
+impl From<bool> for Property {
+    fn from(value: bool) -> Self {
+        Self::Boolean(value)
+    }
+}
+
 impl From<f32> for Property {
     fn from(value: f32) -> Self {
         Self::Float(value)
@@ -708,6 +4355,56 @@ impl From<String> for Property {
     }
 }
 
+/// Which part of a layer stays in place when it is resized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Anchor {
+    /// The top left corner.
+    #[default]
+    TopLeft,
+    /// The middle of the top edge.
+    Top,
+    /// The top right corner.
+    TopRight,
+    /// The middle of the left edge.
+    Left,
+    /// The center.
+    Center,
+    /// The middle of the right edge.
+    Right,
+    /// The bottom left corner.
+    BottomLeft,
+    /// The middle of the bottom edge.
+    Bottom,
+    /// The bottom right corner.
+    BottomRight,
+}
+
+impl Anchor {
+    /// How far along each axis the anchor is, in halves.
+    fn factors(self) -> (usize, usize) {
+        match self {
+            Anchor::TopLeft => (0, 0),
+            Anchor::Top => (1, 0),
+            Anchor::TopRight => (2, 0),
+            Anchor::Left => (0, 1),
+            Anchor::Center => (1, 1),
+            Anchor::Right => (2, 1),
+            Anchor::BottomLeft => (0, 2),
+            Anchor::Bottom => (1, 2),
+            Anchor::BottomRight => (2, 2),
+        }
+    }
+}
+
+/// Both interpretations of a [`Tile`], as returned by [`Tile::view`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TileView {
+    /// Identifier.
+    pub id: u16,
+    /// XY position, as the bytes of the ID in big endian order.
+    pub position: [u8; 2],
+}
+
 #[derive(Copy, Clone)]
 #[repr(C)]
 /// A union representing a tile in a tilemap.
@@ -727,6 +4424,126 @@ unsafe impl Zeroable for Tile {}
 unsafe impl Pod for Tile {}
 
 impl Tile {
+    /// The tile default, representing an empty cell.
+    pub const EMPTY: Tile = Tile { id: 0xFFFF };
+
+    /// Constructs a tile from its ID.
+    ///
+    /// ```rust
+    /// # use ct_tilemap::{Layer, Tile};
+    /// let mut layer = Layer::new();
+    /// layer.resize(2, 2);
+    /// layer[(1, 0)] = Tile::from_id(0x1234);
+    /// assert_eq!(layer[(1, 0)].id(), 0x1234);
+    /// assert!(layer[(0, 0)].is_empty());
+    /// ```
+    #[inline]
+    #[must_use]
+    pub const fn from_id(id: u16) -> Tile {
+        Tile { id }
+    }
+
+    /// Constructs a tile from its position.
+    #[inline]
+    #[must_use]
+    pub const fn from_position(position: [u8; 2]) -> Tile {
+        Tile { position }
+    }
+
+    /// Constructs a tile from a position in big endian order,
+    /// so that [`Tile::id`] reads the bytes in that order on every platform.
+    ///
+    /// ```rust
+    /// # use ct_tilemap::Tile;
+    /// let tile = Tile::with_position_be([0x12, 0x34]);
+    /// assert_eq!(tile.id(), 0x1234);
+    /// assert_eq!(tile.view().position, [0x12, 0x34]);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub const fn with_position_be(position: [u8; 2]) -> Tile {
+        Tile::from_id(u16::from_be_bytes(position))
+    }
+
+    /// Constructs a tile from the two bytes that store it in a file.
+    ///
+    /// Files store the ID in big endian order, which is the position in X, Y order,
+    /// so this is the same on every platform.
+    #[inline]
+    pub(crate) const fn from_file_bytes(bytes: [u8; 2]) -> Tile {
+        Tile::with_position_be(bytes)
+    }
+
+    /// Returns the two bytes that store the tile in a file,
+    /// the inverse of [`Tile::from_file_bytes`].
+    #[inline]
+    pub(crate) fn to_file_bytes(self) -> [u8; 2] {
+        self.id().to_be_bytes()
+    }
+
+    /// Returns both interpretations of the tile,
+    /// with the position in big endian order regardless of the platform.
+    ///
+    /// Unlike [`Tile::position`], the axes of the position never swap between platforms.
+    #[inline]
+    #[must_use]
+    pub fn view(&self) -> TileView {
+        let id = self.id();
+        TileView {
+            id,
+            position: id.to_be_bytes(),
+        }
+    }
+
+    /// Constructs a tile from the X and Y coordinates of its tile in the tileset.
+    ///
+    /// The file stores `x` then `y`, so `x` is the high byte of [`Tile::id`]
+    /// on every platform.
+    ///
+    /// ```rust
+    /// # use ct_tilemap::Tile;
+    /// let tile = Tile::from_tile_coords(0x12, 0x34);
+    /// assert_eq!(tile.id(), 0x1234);
+    /// assert_eq!(tile.tile_coords(), (0x12, 0x34));
+    /// ```
+    #[inline]
+    #[must_use]
+    pub const fn from_tile_coords(x: u8, y: u8) -> Tile {
+        Tile::with_position_be([x, y])
+    }
+
+    /// Returns the X and Y coordinates of the tile in the tileset,
+    /// the inverse of [`Tile::from_tile_coords`].
+    ///
+    /// Unlike [`Tile::position`], this is the same on every platform.
+    #[inline]
+    #[must_use]
+    pub fn tile_coords(&self) -> (u8, u8) {
+        let [x, y] = self.id().to_be_bytes();
+        (x, y)
+    }
+
+    /// Returns a wrapper that formats the tile with both its ID and its [`tile_coords`](Tile::tile_coords),
+    /// for tiles that store a position in their tileset.
+    ///
+    /// ```rust
+    /// # use ct_tilemap::Tile;
+    /// let tile = Tile::from_tile_coords(5, 3);
+    /// assert_eq!(format!("{:?}", tile.debug_position()), "0503 (pos [5, 3])");
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn debug_position(&self) -> impl core::fmt::Debug {
+        formatting::TilePosition(*self)
+    }
+
+    /// Returns whether this is the tile default (`0xFFFF`).
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.id() == 0xFFFF
+    }
+
     /// Safely returns the tile's ID.
     // SAFETY: The size and alignment of u16
     // is the same as the struct.
@@ -762,7 +4579,13 @@ impl Tile {
 
 impl Default for Tile {
     fn default() -> Self {
-        Self { id: 0xFFFF }
+        Self::EMPTY
+    }
+}
+
+impl From<u16> for Tile {
+    fn from(id: u16) -> Self {
+        Self::from_id(id)
     }
 }
 
@@ -774,8 +4597,16 @@ impl PartialEq for Tile {
     }
 }
 
+impl Eq for Tile {}
+
+impl Hash for Tile {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id().hash(state);
+    }
+}
+
 /// A sublayer within a layer of a tilemap.
-#[derive(Clone, PartialEq, Eq, Default)]
+#[derive(Clone, PartialEq, Eq, Hash, Default)]
 pub struct SubLayer {
     pub(crate) data: Vec<u8>,
     default_value: [u8; 4],
@@ -794,7 +4625,65 @@ impl SubLayer {
     ///
     /// # Panics
     /// Panics if the resulting area overflows a u32.
+    /// Use [`SubLayer::try_resize`] for sizes that aren't known to fit,
+    /// like those from untrusted input.
     pub fn resize(&mut self, width: u32, height: u32) {
+        self.resize_anchored(width, height, Anchor::TopLeft);
+    }
+
+    /// Resize the sublayer like [`SubLayer::resize`],
+    /// leaving it as it was if the resulting area overflows a u32.
+    ///
+    /// # Errors
+    /// Errors if the resulting area overflows a u32.
+    pub fn try_resize(&mut self, width: u32, height: u32) -> Result<(), AreaOverflow> {
+        AreaOverflow::check(width, height)?;
+        self.resize(width, height);
+        Ok(())
+    }
+
+    /// Resize the sublayer, keeping the given anchor of the sublayer in place
+    /// and filling empty cells with the sublayer's default value.
+    ///
+    /// Unless the anchor is [`Anchor::TopLeft`], this always reallocates the data buffer.
+    ///
+    /// # Sanity
+    /// The layer this is put into should be the same size as the new size.
+    ///
+    /// # Panics
+    /// Panics if the resulting area overflows a u32.
+    /// Check the size with [`SubLayer::try_resize`] first if it isn't known to fit.
+    pub fn resize_anchored(&mut self, width: u32, height: u32, anchor: Anchor) {
+        assert!(
+            width.checked_mul(height).is_some(),
+            "sublayer area overflows a u32"
+        );
+        if anchor == Anchor::TopLeft || width == 0 || height == 0 {
+            self.resize_top_left(width, height);
+            return;
+        }
+        self.data = grid::resize_anchored(
+            &self.data,
+            (self.width as usize, self.height as usize),
+            (width as usize, height as usize),
+            anchor.factors(),
+            &self.default_value[..self.cell_size as usize],
+        );
+        self.width = width;
+        self.height = height;
+    }
+
+    /// Free any memory the sublayer's data buffer holds past its contents,
+    /// such as what's left over after resizing it down.
+    ///
+    /// Sublayers only ever compare by their contents, so this doesn't affect equality.
+    pub fn shrink_to_fit(&mut self) {
+        self.data.shrink_to_fit();
+    }
+
+    /// Resize the sublayer, keeping the top left corner in place,
+    /// and reallocating as little as possible.
+    fn resize_top_left(&mut self, width: u32, height: u32) {
         if (self.width == width && self.height == height)
             || ((self.width == 0 || self.height == 0) && (width == 0 || height == 0))
         {
@@ -813,24 +4702,17 @@ impl SubLayer {
             // Construct
             self.width = width;
             self.height = height;
-            self.data = iter::repeat(default)
-                .take((width * height) as usize)
-                .flatten()
-                .copied()
-                .collect();
+            self.data = default.repeat(width as usize * height as usize);
             return;
         }
         if self.height > height {
             // Remove rows
             self.data
-                .truncate((self.width * height * self.cell_size as u32) as usize);
+                .truncate(self.width as usize * height as usize * self.cell_size as usize);
         } else if self.height < height {
             // Add rows
-            self.data.extend(
-                iter::repeat(default)
-                    .take((self.width * (height - self.height)) as usize)
-                    .flatten(),
-            );
+            self.data
+                .extend(default.repeat(self.width as usize * (height - self.height) as usize));
         }
         if self.width != width {
             let chunks = self
@@ -841,8 +4723,7 @@ impl SubLayer {
                 chunks
                     .flat_map(|chunk| {
                         chunk.iter().copied().chain(
-                            iter::repeat(default)
-                                .take((width - self.width) as usize)
+                            iter::repeat_n(default, (width - self.width) as usize)
                                 .flatten()
                                 .copied(),
                         )
@@ -881,6 +4762,66 @@ impl SubLayer {
         self.height
     }
 
+    /// Returns the default value of the sublayer, which is one cell long.
+    #[inline]
+    #[must_use]
+    pub fn default_value(&self) -> &[u8] {
+        &self.default_value[..self.cell_size as usize]
+    }
+
+    /// Returns whether the sublayer is the given size, and holds exactly as much data as that.
+    fn is_consistent_with(&self, width: u32, height: u32) -> bool {
+        (self.width, self.height) == (width, height)
+            && self.data.len() == width as usize * height as usize * self.cell_size as usize
+    }
+
+    /// Set the size of the sublayer without moving any cells,
+    /// truncating or padding its data to the new size.
+    fn repair(&mut self, width: u32, height: u32) {
+        let cell = self.cell_size as usize;
+        let area = width as usize * height as usize;
+        // Drop any partial cell at the end, then truncate or pad by whole cells
+        let cells = self.data.len().checked_div(cell).unwrap_or(0);
+        self.data.truncate(cells.min(area) * cell);
+        let missing = area.saturating_sub(cells);
+        self.data
+            .extend(iter::repeat_n(&self.default_value[..cell], missing).flatten());
+        self.width = width;
+        self.height = height;
+    }
+
+    /// Returns the raw data of the sublayer, with the cells in row-major order.
+    ///
+    /// This is always `width * height * cell_size` bytes long.
+    #[inline]
+    #[must_use]
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Set the default value of the sublayer, resizing all cells to its length,
+    /// as with [`SubLayer::set_default`].
+    ///
+    /// Returns the positions of the cells which had non-zero bytes cut off
+    /// because the new default is smaller, in row-major order.
+    /// Growing the cells never loses data, so this is empty then.
+    pub fn set_default_preserving(&mut self, default: &[u8]) -> Vec<(u32, u32)> {
+        let new_size = default.len().min(4);
+        let lost = if new_size < self.cell_size as usize {
+            self.iter_cells()
+                .filter(|(_, cell)| {
+                    let dropped = cell.get(new_size..).unwrap_or_default();
+                    dropped.iter().any(|&byte| byte != 0)
+                })
+                .map(|(position, _)| position)
+                .collect()
+        } else {
+            Vec::new()
+        };
+        self.set_default(default);
+        lost
+    }
+
     /// Set the default value of the sublayer, resizing all cells to its length.
     ///
     /// The default value is truncated to 4 bytes if larger.
@@ -890,6 +4831,8 @@ impl SubLayer {
     /// If the new default is smaller, all cells are truncated to its length.
     ///
     /// This will *only* not reallocate if the length of the new default is the same as the old one!
+    ///
+    /// Use [`SubLayer::set_default_preserving`] to find out which cells lost data.
     pub fn set_default(&mut self, default: &[u8]) {
         let old_size = self.cell_size as usize;
         let new_size = default.len().min(4);
@@ -913,7 +4856,7 @@ impl SubLayer {
         if old_size == 0 {
             // Need to construct
             self.data.resize(
-                (self.width * self.height * self.cell_size as u32) as usize,
+                self.width as usize * self.height as usize * self.cell_size as usize,
                 0,
             );
             return;
@@ -925,8 +4868,7 @@ impl SubLayer {
                 .chunks(old_size)
                 .flat_map(|cell| {
                     // Need to 0-pad
-                    cell.iter()
-                        .chain(iter::repeat(&0).take(new_size - old_size))
+                    cell.iter().chain(iter::repeat_n(&0, new_size - old_size))
                 })
                 .copied()
                 .collect()
@@ -943,29 +4885,171 @@ impl SubLayer {
         };
     }
 
+    /// Set the default value of the sublayer like [`SubLayer::set_default`],
+    /// but leave the sublayer unchanged and return an error
+    /// if the default value is longer than 4 bytes, instead of truncating it.
+    ///
+    /// # Errors
+    /// Errors if the default value is longer than 4 bytes.
+    pub fn try_set_default(&mut self, default: &[u8]) -> Result<(), DefaultTooLong> {
+        if default.len() > 4 {
+            return Err(DefaultTooLong { len: default.len() });
+        }
+        self.set_default(default);
+        Ok(())
+    }
+
     /// Get a cell by position.
     /// Returns None if out of bounds.
     #[must_use]
-    pub fn get(&self, (x, y): (u32, u32)) -> Option<&[u8]> {
-        if x >= self.width || y >= self.height {
-            return None;
-        }
-        let size = self.cell_size as usize;
-        let start = (y * self.width + x) as usize * size;
-        let end = start + size;
-        Some(&self.data[start..end])
+    pub fn get(&self, position: (u32, u32)) -> Option<&[u8]> {
+        self.data.get(self.cell_range(position)?)
     }
 
     /// Get a cell by position, mutably.
     /// Returns None if out of bounds
-    pub fn get_mut(&mut self, (x, y): (u32, u32)) -> Option<&mut [u8]> {
+    pub fn get_mut(&mut self, position: (u32, u32)) -> Option<&mut [u8]> {
+        let range = self.cell_range(position)?;
+        self.data.get_mut(range)
+    }
+
+    /// Find where the bytes of a cell are in the data,
+    /// returning `None` if it's out of bounds or the index would overflow.
+    fn cell_range(&self, (x, y): (u32, u32)) -> Option<Range<usize>> {
         if x >= self.width || y >= self.height {
             return None;
         }
         let size = self.cell_size as usize;
-        let start = (y * self.width + x) as usize * size;
-        let end = start + size;
-        Some(&mut self.data[start..end])
+        let start = (y as usize)
+            .checked_mul(self.width as usize)?
+            .checked_add(x as usize)?
+            .checked_mul(size)?;
+        Some(start..start.checked_add(size)?)
+    }
+
+    /// Find where the bytes of a cell are in the data, panicking if it's out of bounds.
+    fn expect_cell_range(&self, (x, y): (u32, u32)) -> Range<usize> {
+        match self.cell_range((x, y)) {
+            Some(range) if range.end <= self.data.len() => range,
+            _ => panic!(
+                "position ({x}, {y}) is out of bounds of the {}x{} sublayer",
+                self.width, self.height
+            ),
+        }
+    }
+
+    /// Get a cell by position as a byte.
+    /// Returns None if out of bounds, or if the cell size isn't 1.
+    #[must_use]
+    pub fn get_u8(&self, position: (u32, u32)) -> Option<u8> {
+        let cell = self.get(position)?;
+        Some(u8::from_le_bytes(cell.try_into().ok()?))
+    }
+
+    /// Get a cell by position as a little endian `u16`.
+    /// Returns None if out of bounds, or if the cell size isn't 2.
+    #[must_use]
+    pub fn get_u16(&self, position: (u32, u32)) -> Option<u16> {
+        let cell = self.get(position)?;
+        Some(u16::from_le_bytes(cell.try_into().ok()?))
+    }
+
+    /// Get a cell by position as a little endian `u32`.
+    /// Returns None if out of bounds, or if the cell size isn't 4.
+    #[must_use]
+    pub fn get_u32(&self, position: (u32, u32)) -> Option<u32> {
+        let cell = self.get(position)?;
+        Some(u32::from_le_bytes(cell.try_into().ok()?))
+    }
+
+    /// Returns every cell of the sublayer as a byte, in row-major order.
+    /// This borrows the data as-is.
+    ///
+    /// Returns None if the cell size isn't 1.
+    #[must_use]
+    pub fn as_u8_grid(&self) -> Option<&[u8]> {
+        (self.cell_size == 1).then_some(self.data.as_slice())
+    }
+
+    /// Returns every cell of the sublayer as a little endian `u16`, in row-major order.
+    ///
+    /// Returns None if the cell size isn't 2.
+    #[must_use]
+    pub fn as_u16_grid(&self) -> Option<Vec<u16>> {
+        (self.cell_size == 2).then(|| {
+            self.data
+                .chunks_exact(2)
+                .map(|cell| u16::from_le_bytes([cell[0], cell[1]]))
+                .collect()
+        })
+    }
+
+    /// Returns every cell of the sublayer as a little endian `u32`, in row-major order.
+    ///
+    /// Returns None if the cell size isn't 4.
+    #[must_use]
+    pub fn as_u32_grid(&self) -> Option<Vec<u32>> {
+        (self.cell_size == 4).then(|| {
+            self.data
+                .chunks_exact(4)
+                .map(|cell| u32::from_le_bytes([cell[0], cell[1], cell[2], cell[3]]))
+                .collect()
+        })
+    }
+
+    /// Set a cell by position to a byte.
+    /// Returns false if out of bounds, or if the cell size isn't 1.
+    pub fn set_u8(&mut self, position: (u32, u32), value: u8) -> bool {
+        self.set_bytes(position, &value.to_le_bytes())
+    }
+
+    /// Set a cell by position to a little endian `u16`.
+    /// Returns false if out of bounds, or if the cell size isn't 2.
+    pub fn set_u16(&mut self, position: (u32, u32), value: u16) -> bool {
+        self.set_bytes(position, &value.to_le_bytes())
+    }
+
+    /// Set a cell by position to a little endian `u32`.
+    /// Returns false if out of bounds, or if the cell size isn't 4.
+    pub fn set_u32(&mut self, position: (u32, u32), value: u32) -> bool {
+        self.set_bytes(position, &value.to_le_bytes())
+    }
+
+    /// Set a cell by position, without panicking.
+    ///
+    /// # Errors
+    /// Errors if the position is out of bounds,
+    /// or if the cell isn't the same length as the sublayer's cell size.
+    pub fn set(&mut self, (x, y): (u32, u32), cell: &[u8]) -> Result<(), SubLayerSetError> {
+        if cell.len() != self.cell_size as usize {
+            return Err(SubLayerSetError::WrongLength {
+                expected: self.cell_size,
+                actual: cell.len(),
+            });
+        }
+        let (width, height) = (self.width, self.height);
+        let Some(slot) = self.get_mut((x, y)) else {
+            return Err(OutOfBounds {
+                x,
+                y,
+                width,
+                height,
+            }
+            .into());
+        };
+        slot.copy_from_slice(cell);
+        Ok(())
+    }
+
+    /// Copy the bytes into a cell, if it exists and is the same length.
+    fn set_bytes(&mut self, position: (u32, u32), bytes: &[u8]) -> bool {
+        match self.get_mut(position) {
+            Some(cell) if cell.len() == bytes.len() => {
+                cell.copy_from_slice(bytes);
+                true
+            }
+            _ => false,
+        }
     }
 
     /// Constructs a new instance from the default.
@@ -975,6 +5059,24 @@ impl SubLayer {
         Self::default()
     }
 
+    /// Constructs a sublayer of the given size, with every cell set to the default value.
+    ///
+    /// The default value is truncated to 4 bytes if larger.
+    ///
+    /// # Sanity
+    /// The layer this is put into should be the same size.
+    ///
+    /// # Panics
+    /// Panics if the resulting area overflows a u32.
+    /// Check the size with [`SubLayer::try_resize`] on an empty sublayer if it isn't known to fit.
+    #[must_use]
+    pub fn with_size(default_value: &[u8], width: u32, height: u32) -> Self {
+        let mut sublayer = Self::default();
+        sublayer.set_default(default_value);
+        sublayer.resize(width, height);
+        sublayer
+    }
+
     /// Creates an iterator over each cell of the sublayer, returning a slice.
     pub fn iter(&self) -> impl Iterator<Item = &[u8]> {
         self.data.chunks(self.cell_size as usize)
@@ -984,6 +5086,117 @@ impl SubLayer {
     pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut [u8]> {
         self.data.chunks_mut(self.cell_size as usize)
     }
+
+    /// Creates an iterator over each cell of the sublayer in row-major order,
+    /// returning its position and a slice.
+    ///
+    /// Each slice is exactly [`cell_size`](SubLayer::cell_size) bytes long,
+    /// so they are empty if the cell size is 0.
+    pub fn iter_cells(&self) -> impl Iterator<Item = ((u32, u32), &[u8])> {
+        // There is no data if the cell size is 0, so every slice defaults to being empty
+        let mut cells = self.data.chunks_exact(self.cell_size.max(1) as usize);
+        self.positions()
+            .map(move |position| (position, cells.next().unwrap_or_default()))
+    }
+
+    /// Creates an iterator over each cell of the sublayer in row-major order,
+    /// returning its position and a mutable slice.
+    ///
+    /// Each slice is exactly [`cell_size`](SubLayer::cell_size) bytes long,
+    /// so they are empty if the cell size is 0.
+    pub fn iter_cells_mut(&mut self) -> impl Iterator<Item = ((u32, u32), &mut [u8])> {
+        let positions = self.positions();
+        let mut cells = self.data.chunks_exact_mut(self.cell_size.max(1) as usize);
+        positions.map(move |position| (position, cells.next().unwrap_or_default()))
+    }
+
+    /// Creates an iterator over every position in the sublayer, in row-major order.
+    fn positions(&self) -> impl Iterator<Item = (u32, u32)> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+    }
+
+    /// Mirror the sublayer's cells from left to right.
+    ///
+    /// The bytes within each cell keep their order.
+    pub fn flip_horizontal(&mut self) {
+        grid::flip_horizontal(&mut self.data, self.width as usize, self.cell_size as usize);
+    }
+
+    /// Mirror the sublayer's cells from top to bottom.
+    pub fn flip_vertical(&mut self) {
+        grid::flip_vertical(&mut self.data, self.width as usize, self.cell_size as usize);
+    }
+
+    /// Move every cell of the sublayer by `dx` cells to the right and `dy` cells down,
+    /// like [`Layer::shift`].
+    pub fn shift(&mut self, dx: i32, dy: i32, wrap: bool) {
+        let size = (self.width as usize, self.height as usize);
+        let delta = (dx as i64, dy as i64);
+        let default = &self.default_value[..self.cell_size as usize];
+        self.data = grid::shift(&self.data, size, delta, wrap, default);
+    }
+
+    /// Copy a `w` by `h` region of the sublayer starting at `(x, y)` into a new sublayer.
+    ///
+    /// Returns None if the region is out of bounds.
+    #[must_use]
+    pub fn crop(&self, x: u32, y: u32, w: u32, h: u32) -> Option<SubLayer> {
+        let region = grid::region((self.width, self.height), (x, y, w, h))?;
+        let mut sublayer = SubLayer {
+            data: Vec::new(),
+            default_value: self.default_value,
+            cell_size: self.cell_size,
+            width: 0,
+            height: 0,
+        };
+        if w == 0 || h == 0 {
+            return Some(sublayer);
+        }
+        sublayer.data = grid::crop(
+            &self.data,
+            self.width as usize,
+            self.cell_size as usize,
+            region,
+        );
+        sublayer.width = w;
+        sublayer.height = h;
+        Some(sublayer)
+    }
+
+    /// Rotate the sublayer's cells clockwise by 90 degrees, swapping its width and height.
+    ///
+    /// # Sanity
+    /// The layer this is put into should be rotated the same way.
+    pub fn rotate_cw(&mut self) {
+        if self.width == 0 || self.height == 0 {
+            return;
+        }
+        self.data = grid::rotate_cw(
+            &self.data,
+            self.width as usize,
+            self.height as usize,
+            self.cell_size as usize,
+        );
+        (self.width, self.height) = (self.height, self.width);
+    }
+
+    /// Rotate the sublayer's cells counterclockwise by 90 degrees, swapping its width and height.
+    ///
+    /// # Sanity
+    /// The layer this is put into should be rotated the same way.
+    pub fn rotate_ccw(&mut self) {
+        if self.width == 0 || self.height == 0 {
+            return;
+        }
+        self.data = grid::rotate_ccw(
+            &self.data,
+            self.width as usize,
+            self.height as usize,
+            self.cell_size as usize,
+        );
+        (self.width, self.height) = (self.height, self.width);
+    }
 }
 
 impl Index<(u32, u32)> for SubLayer {
@@ -993,11 +5206,8 @@ impl Index<(u32, u32)> for SubLayer {
     ///
     /// # Panics
     /// Panics if index is out of bounds.
-    fn index(&self, (x, y): (u32, u32)) -> &Self::Output {
-        let size = self.cell_size as usize;
-        let start = (y * self.width + x) as usize * size;
-        let end = start + size;
-        &self.data[start..end]
+    fn index(&self, position: (u32, u32)) -> &Self::Output {
+        &self.data[self.expect_cell_range(position)]
     }
 }
 
@@ -1006,16 +5216,15 @@ impl IndexMut<(u32, u32)> for SubLayer {
     ///
     /// # Panics
     /// Panics if index is out of bounds.
-    fn index_mut(&mut self, (x, y): (u32, u32)) -> &mut Self::Output {
-        let size = self.cell_size as usize;
-        let start = (y * self.width + x) as usize * size;
-        let end = start + size;
-        &mut self.data[start..end]
+    fn index_mut(&mut self, position: (u32, u32)) -> &mut Self::Output {
+        let range = self.expect_cell_range(position);
+        &mut self.data[range]
     }
 }
 
 /// A link to a sublayer within a layer.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SubLayerLink {
     /// Which sublayer is this layer's tileset linked to?
     pub tileset: u8,
@@ -1026,20 +5235,73 @@ pub struct SubLayerLink {
 }
 
 impl SubLayerLink {
+    /// The value of a link that isn't linked to any sublayer.
+    pub const UNLINKED: u8 = 0xFF;
+
     /// Constructs a new instance from the default.
     #[inline]
     #[must_use]
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Returns the index of the sublayer the tileset is linked to, if it's linked.
+    #[inline]
+    #[must_use]
+    pub fn tileset_index(&self) -> Option<usize> {
+        link_index(self.tileset)
+    }
+
+    /// Returns the index of the sublayer the animation is linked to, if it's linked.
+    #[inline]
+    #[must_use]
+    pub fn animation_index(&self) -> Option<usize> {
+        link_index(self.animation)
+    }
+
+    /// Returns the index of the sublayer the animation frames are linked to, if they're linked.
+    #[inline]
+    #[must_use]
+    pub fn animation_frame_index(&self) -> Option<usize> {
+        link_index(self.animation_frame)
+    }
+
+    /// Link the tileset to a sublayer, or unlink it with `None`.
+    ///
+    /// `Some(0xFF)` is the same as `None`, as there's no way to store a link to that sublayer.
+    #[inline]
+    pub fn set_tileset_link(&mut self, index: Option<u8>) {
+        self.tileset = index.unwrap_or(Self::UNLINKED);
+    }
+
+    /// Link the animation to a sublayer, or unlink it with `None`.
+    ///
+    /// `Some(0xFF)` is the same as `None`, as there's no way to store a link to that sublayer.
+    #[inline]
+    pub fn set_animation_link(&mut self, index: Option<u8>) {
+        self.animation = index.unwrap_or(Self::UNLINKED);
+    }
+
+    /// Link the animation frames to a sublayer, or unlink them with `None`.
+    ///
+    /// `Some(0xFF)` is the same as `None`, as there's no way to store a link to that sublayer.
+    #[inline]
+    pub fn set_animation_frame_link(&mut self, index: Option<u8>) {
+        self.animation_frame = index.unwrap_or(Self::UNLINKED);
+    }
+}
+
+/// Turn a raw link into a sublayer index.
+fn link_index(link: u8) -> Option<usize> {
+    (link != SubLayerLink::UNLINKED).then_some(link as usize)
 }
 
 impl Default for SubLayerLink {
     fn default() -> Self {
         Self {
-            tileset: 0xFF,
-            animation: 0xFF,
-            animation_frame: 0xFF,
+            tileset: Self::UNLINKED,
+            animation: Self::UNLINKED,
+            animation_frame: Self::UNLINKED,
         }
     }
 }
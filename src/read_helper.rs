@@ -1,7 +1,63 @@
-use byteorder::{LittleEndian, ReadBytesExt};
-use libflate::zlib::Decoder;
-use std::io;
-use std::io::{Cursor, Read};
+use crate::io::{self, Cursor, Read, ReadBytesExt, Seek, SeekFrom};
+use crate::prelude::*;
+use crate::{Framing, ReadError, ReadOptions, ReadScratch};
+use alloc::borrow::Cow;
+use byteorder::LittleEndian;
+
+/// A reader that keeps track of how many bytes have been read through it.
+pub(crate) struct Counter<R> {
+    inner: R,
+    pub(crate) position: u64,
+    /// The position the stream ends at, if known.
+    pub(crate) end: Option<u64>,
+}
+
+impl<R> Counter<R> {
+    pub(crate) fn new(inner: R) -> Self {
+        Self::at(inner, 0)
+    }
+
+    /// Start counting from a given position, for readers that were seeked into the middle of a file.
+    pub(crate) fn at(inner: R, position: u64) -> Self {
+        Counter {
+            inner,
+            position,
+            end: None,
+        }
+    }
+
+    /// Returns whether there could be enough of the stream left
+    /// to hold `count` items that are at least `size` bytes each.
+    ///
+    /// This is always `true` if the end of the stream isn't known.
+    pub(crate) fn has_room(&self, count: u64, size: u64) -> bool {
+        self.end
+            .is_none_or(|end| count.saturating_mul(size) <= end.saturating_sub(self.position))
+    }
+}
+
+impl<R: Seek> Counter<R> {
+    /// Seek forwards past some bytes, without reading them.
+    pub(crate) fn skip(&mut self, amount: u64) -> io::Result<()> {
+        let Ok(relative) = i64::try_from(amount) else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "cannot skip that many bytes",
+            ));
+        };
+        self.inner.seek(SeekFrom::Current(relative))?;
+        self.position += amount;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for Counter<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
 
 pub(crate) fn read_short_string(mut r: impl Read) -> io::Result<Vec<u8>> {
     let length = r.read_u8()? as usize + 1;
@@ -11,6 +67,21 @@ pub(crate) fn read_short_string(mut r: impl Read) -> io::Result<Vec<u8>> {
     Ok(bytes)
 }
 
+/// Decode a property key or tileset path with the encoding in the options,
+/// along with whether anything in it had to be replaced.
+#[cfg_attr(not(feature = "encoding_rs"), allow(unused_variables))]
+pub(crate) fn decode_string(bytes: &[u8], options: &ReadOptions) -> (String, bool) {
+    #[cfg(feature = "encoding_rs")]
+    if let Some(encoding) = options.encoding {
+        let (string, lossy) = encoding.decode_without_bom_handling(bytes);
+        return (string.into_owned(), lossy);
+    }
+    match String::from_utf8_lossy(bytes) {
+        Cow::Borrowed(string) => (string.to_owned(), false),
+        Cow::Owned(string) => (string, true),
+    }
+}
+
 pub(crate) fn read_long_string(mut r: impl Read) -> io::Result<Vec<u8>> {
     let length = r.read_u32::<LittleEndian>()? as usize + 1;
     if length > isize::MAX as usize {
@@ -26,20 +97,100 @@ pub(crate) fn read_long_string(mut r: impl Read) -> io::Result<Vec<u8>> {
     Ok(bytes)
 }
 
-pub(crate) fn read_compressed(mut r: impl Read) -> io::Result<Vec<u8>> {
-    let length = r.read_u32::<LittleEndian>()? as usize;
-    if length > isize::MAX as usize {
+/// Read into the buffer until it's full or the reader runs out, returning how much was read.
+pub(crate) fn read_up_to(mut r: impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        match r.read(&mut buf[read..]) {
+            Ok(0) => break,
+            Ok(n) => read += n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(read)
+}
+
+pub(crate) fn read_block(r: impl Read, length: u32) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    read_block_into(r, length, &mut bytes)?;
+    Ok(bytes)
+}
+
+/// Read a block into a buffer, replacing what was in it.
+fn read_block_into(r: impl Read, length: u32, bytes: &mut Vec<u8>) -> io::Result<()> {
+    bytes.clear();
+    // Read through a limit, so a bogus length can't allocate a huge buffer up front
+    r.take(length as u64).read_to_end(bytes)?;
+    if bytes.len() != length as usize {
         return Err(io::Error::new(
-            io::ErrorKind::InvalidData,
-            "compressed data size was too large to fit in a byte vector",
+            io::ErrorKind::UnexpectedEof,
+            "block ended before its declared size",
         ));
     }
-    // Decode data using libflate
-    let mut encoded_buf = vec![0; length];
-    let encoded = encoded_buf.as_mut_slice();
-    r.read_exact(encoded)?;
-    let mut decoder = Decoder::new(Cursor::new(encoded))?;
-    let mut decoded_buf = Vec::new();
-    decoder.read_to_end(&mut decoded_buf)?;
-    Ok(decoded_buf)
+    Ok(())
+}
+
+/// Read a block of compressed data into `scratch.encoded`, without decompressing it.
+pub(crate) fn read_encoded(mut r: impl Read, scratch: &mut ReadScratch) -> io::Result<()> {
+    let length = r.read_u32::<LittleEndian>()?;
+    // This doesn't allocate the declared length up front, so a bogus one can't exhaust memory
+    read_block_into(&mut r, length, &mut scratch.encoded)
+}
+
+/// Read and decompress a block of compressed data into the scratch buffers,
+/// returning how it was framed, with the data left in `scratch.decoded`.
+/// Returns `None` if it would decompress to more than `limit` bytes.
+///
+/// The data is decoded as zlib first, falling back to raw deflate if that fails,
+/// and raises [`ReadError::Decompression`] if neither works.
+pub(crate) fn read_compressed(
+    r: impl Read,
+    limit: u64,
+    scratch: &mut ReadScratch,
+) -> Result<Option<Framing>, ReadError> {
+    read_encoded(r, scratch)?;
+    let (encoded, decoded) = (&scratch.encoded, &mut scratch.decoded);
+    let framing = match inflate(encoded, Framing::Zlib, limit, decoded) {
+        Ok(fits) => fits.then_some(Framing::Zlib),
+        // If it isn't raw deflate either, the zlib error is the more useful one
+        Err(err) => match inflate(encoded, Framing::RawDeflate, limit, decoded) {
+            Ok(fits) => fits.then_some(Framing::RawDeflate),
+            Err(_) => return Err(ReadError::Decompression(err)),
+        },
+    };
+    Ok(framing)
+}
+
+/// Decompress some data with the given framing into a buffer, replacing what was in it.
+/// Returns `false` if it would decompress to more than `limit` bytes.
+fn inflate(
+    encoded: &[u8],
+    framing: Framing,
+    limit: u64,
+    decoded: &mut Vec<u8>,
+) -> io::Result<bool> {
+    decoded.clear();
+    // Read one byte past the limit, to tell if it was hit
+    decode(Cursor::new(encoded), framing)?
+        .take(limit.saturating_add(1))
+        .read_to_end(decoded)?;
+    Ok(decoded.len() as u64 <= limit)
+}
+
+#[cfg(feature = "libflate-backend")]
+fn decode<'a>(encoded: impl Read + 'a, framing: Framing) -> io::Result<Box<dyn Read + 'a>> {
+    Ok(match framing {
+        Framing::Zlib => Box::new(libflate::zlib::Decoder::new(encoded)?),
+        Framing::RawDeflate => Box::new(libflate::deflate::Decoder::new(encoded)),
+    })
+}
+
+#[cfg(all(feature = "flate2-backend", not(feature = "libflate-backend")))]
+#[allow(clippy::unnecessary_wraps)] // Matches the libflate backend, which can fail here
+fn decode<'a>(encoded: impl Read + 'a, framing: Framing) -> io::Result<Box<dyn Read + 'a>> {
+    Ok(match framing {
+        Framing::Zlib => Box::new(flate2::read::ZlibDecoder::new(encoded)),
+        Framing::RawDeflate => Box::new(flate2::read::DeflateDecoder::new(encoded)),
+    })
 }
@@ -0,0 +1,135 @@
+use crate::prelude::*;
+use crate::{Layer, SubLayer, SubLayerLink, Tile};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+impl Serialize for Tile {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // Unions can't be derived, so go through the ID
+        serializer.serialize_u16(self.id())
+    }
+}
+
+impl<'de> Deserialize<'de> for Tile {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let id = u16::deserialize(deserializer)?;
+        Ok(Tile { id })
+    }
+}
+
+/// The serialized shape of a sublayer, borrowed for serialization.
+#[derive(Serialize)]
+#[serde(rename = "SubLayer")]
+struct SubLayerRef<'a> {
+    default_value: &'a [u8],
+    width: u32,
+    height: u32,
+    data: &'a [u8],
+}
+
+/// The serialized shape of a sublayer, without any of its invariants.
+#[derive(Deserialize)]
+#[serde(rename = "SubLayer")]
+struct RawSubLayer {
+    default_value: Vec<u8>,
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+impl Serialize for SubLayer {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SubLayerRef {
+            default_value: self.default_value(),
+            width: self.width,
+            height: self.height,
+            data: &self.data,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SubLayer {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = RawSubLayer::deserialize(deserializer)?;
+        if raw.default_value.len() > 4 {
+            return Err(de::Error::invalid_length(
+                raw.default_value.len(),
+                &"a default value of at most 4 bytes",
+            ));
+        }
+        // Check the size before building anything, so a huge size can't allocate its whole area
+        let expected = raw
+            .width
+            .checked_mul(raw.height)
+            .and_then(|area| (area as usize).checked_mul(raw.default_value.len()));
+        if expected != Some(raw.data.len()) {
+            return Err(de::Error::invalid_length(
+                raw.data.len(),
+                &"width * height * cell size bytes of data",
+            ));
+        }
+        let mut sublayer = SubLayer::with_size(&raw.default_value, 0, 0);
+        (sublayer.width, sublayer.height, sublayer.data) = (raw.width, raw.height, raw.data);
+        Ok(sublayer)
+    }
+}
+
+/// The serialized shape of a layer, without any of its invariants.
+#[derive(Deserialize)]
+#[serde(rename = "Layer")]
+struct RawLayer {
+    data: Vec<Tile>,
+    width: u32,
+    height: u32,
+    tileset: u8,
+    collision: u8,
+    offset: (i32, i32),
+    scroll: (f32, f32),
+    wrap: (bool, bool),
+    visible: bool,
+    opacity: f32,
+    tile_dimensions: (u16, u16),
+    sublayers: Vec<SubLayer>,
+    sublayer_link: SubLayerLink,
+    #[serde(default)]
+    default_tile: Tile,
+}
+
+impl<'de> Deserialize<'de> for Layer {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = RawLayer::deserialize(deserializer)?;
+        let area = raw.width.checked_mul(raw.height).map(|area| area as usize);
+        if area != Some(raw.data.len()) {
+            return Err(de::Error::invalid_length(
+                raw.data.len(),
+                &"width * height tiles of data",
+            ));
+        }
+        if let Some(sublayer) = raw
+            .sublayers
+            .iter()
+            .find(|sublayer| (sublayer.width, sublayer.height) != (raw.width, raw.height))
+        {
+            return Err(de::Error::custom(format_args!(
+                "sublayer size {}x{} did not match layer size {}x{}",
+                sublayer.width, sublayer.height, raw.width, raw.height
+            )));
+        }
+        Ok(Layer {
+            data: raw.data,
+            width: raw.width,
+            height: raw.height,
+            tileset: raw.tileset,
+            collision: raw.collision,
+            offset: raw.offset,
+            scroll: raw.scroll,
+            wrap: raw.wrap,
+            visible: raw.visible,
+            opacity: raw.opacity,
+            tile_dimensions: raw.tile_dimensions,
+            sublayers: raw.sublayers,
+            sublayer_link: raw.sublayer_link,
+            default_tile: raw.default_tile,
+        })
+    }
+}
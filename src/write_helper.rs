@@ -1,10 +1,84 @@
-use byteorder::{LittleEndian, WriteBytesExt};
-use libflate::zlib::Encoder;
-use std::io;
-use std::io::{Cursor, Write};
+use crate::io::{self, Seek, SeekFrom, Write, WriteBytesExt};
+use crate::prelude::*;
+use crate::{CompressionLevel, Framing, Layer, Tile, WriteOptions};
+use alloc::borrow::Cow;
+use byteorder::LittleEndian;
 
-pub(crate) fn write_short_string(mut w: impl Write, string: &str) -> io::Result<()> {
-    let mut bytes = string.as_bytes();
+/// A stream that blocks get written to.
+pub(crate) trait Sink: Write {
+    /// Returns the current position in the stream,
+    /// or `None` if it can't seek back to fill in the length of a block.
+    fn position(&mut self) -> io::Result<Option<u64>>;
+
+    /// Go back to write the length of a block at a position, then return to the end.
+    fn patch_length(&mut self, at: u64, length: u32) -> io::Result<()>;
+}
+
+/// A stream that can't seek, so each block is buffered to find its length before writing it.
+pub(crate) struct Buffered<W>(pub(crate) W);
+
+impl<W: Write> Write for Buffered<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl<W: Write> Sink for Buffered<W> {
+    fn position(&mut self) -> io::Result<Option<u64>> {
+        Ok(None)
+    }
+
+    fn patch_length(&mut self, _: u64, _: u32) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "cannot seek back in a buffered stream",
+        ))
+    }
+}
+
+/// A stream that can seek, so each block is written straight to it
+/// and its length is filled in afterwards.
+pub(crate) struct Seekable<W>(pub(crate) W);
+
+impl<W: Write> Write for Seekable<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl<W: Write + Seek> Sink for Seekable<W> {
+    fn position(&mut self) -> io::Result<Option<u64>> {
+        self.0.stream_position().map(Some)
+    }
+
+    fn patch_length(&mut self, at: u64, length: u32) -> io::Result<()> {
+        let end = self.0.stream_position()?;
+        self.0.seek(SeekFrom::Start(at))?;
+        self.0.write_all(&length.to_le_bytes())?;
+        self.0.seek(SeekFrom::Start(end))?;
+        Ok(())
+    }
+}
+
+/// Encode a property key or tileset path with the encoding in the options.
+#[cfg_attr(not(feature = "encoding_rs"), allow(unused_variables))]
+pub(crate) fn encode_string<'a>(string: &'a str, options: &WriteOptions) -> Cow<'a, [u8]> {
+    #[cfg(feature = "encoding_rs")]
+    if let Some(encoding) = options.encoding {
+        return encoding.encode(string).0;
+    }
+    Cow::Borrowed(string.as_bytes())
+}
+
+pub(crate) fn write_short_string(mut w: impl Write, mut bytes: &[u8]) -> io::Result<()> {
     let mut len = bytes.len().min(256);
     if len == 0 {
         return Err(io::Error::new(
@@ -37,12 +111,145 @@ pub(crate) fn write_long_string(mut w: impl Write, string: &[u8]) -> io::Result<
     w.write_all(string)
 }
 
-pub(crate) fn write_compressed(mut w: impl Write, data: &[u8]) -> io::Result<()> {
-    let mut buf = Cursor::new(Vec::new());
-    let mut encoder = Encoder::new(&mut buf)?;
-    encoder.write_all(data)?;
-    encoder.finish().into_result()?;
-    w.write_u32::<LittleEndian>(buf.position() as u32)?;
-    w.write_all(buf.get_ref().as_slice())?;
+/// Convert the tiles of a layer to the file's byte order.
+pub(crate) fn tile_bytes(layer: &Layer) -> Vec<u8> {
+    layer
+        .data
+        .iter()
+        .copied()
+        .flat_map(Tile::to_file_bytes)
+        .collect()
+}
+
+/// Compress the tiles and sublayers of every layer up front, spread across threads.
+///
+/// This returns the blocks of each layer in the order they're written,
+/// which is its tiles and then its sublayers, or nothing for empty layers.
+/// The blocks are the same as compressing each of them with [`encode`].
+#[cfg(feature = "rayon")]
+pub(crate) fn compress_layers(
+    layers: &[&Layer],
+    level: CompressionLevel,
+    framing: Framing,
+) -> io::Result<Vec<Vec<Vec<u8>>>> {
+    use rayon::prelude::*;
+    let block_count = |layer: &Layer| {
+        if layer.is_empty() {
+            0
+        } else {
+            1 + layer.sublayers.len().min(crate::MAX_SUBLAYERS)
+        }
+    };
+    let tiles = layers
+        .iter()
+        .map(|layer| tile_bytes(layer))
+        .collect::<Vec<_>>();
+    let blocks = layers
+        .iter()
+        .zip(&tiles)
+        .flat_map(|(layer, tiles)| {
+            let sublayers = layer
+                .sublayers
+                .iter()
+                .map(|sublayer| sublayer.data.as_slice());
+            core::iter::once(tiles.as_slice())
+                .chain(sublayers)
+                .take(block_count(layer))
+        })
+        .collect::<Vec<_>>();
+    let mut compressed = blocks
+        .par_iter()
+        .map(|data| encode(data, level, framing))
+        .collect::<io::Result<Vec<_>>>()?
+        .into_iter();
+    Ok(layers
+        .iter()
+        .map(|layer| compressed.by_ref().take(block_count(layer)).collect())
+        .collect())
+}
+
+/// Write an already compressed block, prefixed by its length.
+pub(crate) fn write_compressed(mut w: impl Write, buf: &[u8]) -> io::Result<()> {
+    let Ok(len) = u32::try_from(buf.len()) else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "compressed data size was too large to fit in the file",
+        ));
+    };
+    w.write_u32::<LittleEndian>(len)?;
+    w.write_all(buf)?;
     Ok(())
 }
+
+/// Compress a block of data.
+#[cfg(feature = "libflate-backend")]
+pub(crate) fn encode(
+    data: &[u8],
+    level: CompressionLevel,
+    framing: Framing,
+) -> io::Result<Vec<u8>> {
+    use libflate::lz77::DefaultLz77Encoder;
+    use libflate::{deflate, zlib};
+
+    // The zlib and raw deflate options are different types with the same methods
+    macro_rules! options {
+        ($options:ty) => {
+            match level {
+                CompressionLevel::None => <$options>::new().no_compression(),
+                // A smaller window means less searching for matches
+                CompressionLevel::Fast => {
+                    <$options>::with_lz77(DefaultLz77Encoder::with_window_size(1024))
+                        .fixed_huffman_codes()
+                }
+                // libflate's defaults already search the whole window
+                CompressionLevel::Default | CompressionLevel::Best => <$options>::new(),
+            }
+        };
+    }
+    match framing {
+        Framing::Zlib => {
+            let options = options!(zlib::EncodeOptions<DefaultLz77Encoder>);
+            let mut encoder = zlib::Encoder::with_options(Vec::new(), options)?;
+            encoder.write_all(data)?;
+            encoder.finish().into_result()
+        }
+        Framing::RawDeflate => {
+            let options = options!(deflate::EncodeOptions<DefaultLz77Encoder>);
+            let mut encoder = deflate::Encoder::with_options(Vec::new(), options);
+            encoder.write_all(data)?;
+            encoder.finish().into_result()
+        }
+    }
+}
+
+/// Compress a block of data.
+#[cfg(all(feature = "flate2-backend", not(feature = "libflate-backend")))]
+pub(crate) fn encode(
+    data: &[u8],
+    level: CompressionLevel,
+    framing: Framing,
+) -> io::Result<Vec<u8>> {
+    use flate2::{
+        write::{DeflateEncoder, ZlibEncoder},
+        Compression,
+    };
+
+    let compression = match level {
+        CompressionLevel::None => Compression::none(),
+        CompressionLevel::Fast => Compression::fast(),
+        CompressionLevel::Default => Compression::default(),
+        CompressionLevel::Best => Compression::best(),
+    };
+    match framing {
+        Framing::Zlib => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), compression);
+            encoder.write_all(data)?;
+            encoder.finish()
+        }
+        Framing::RawDeflate => {
+            let mut encoder = DeflateEncoder::new(Vec::new(), compression);
+            encoder.write_all(data)?;
+            encoder.finish()
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use ct_tilemap::TileMap;
+use std::io::{Read, Write};
+
+mod common;
+use common::FILE;
+
+fn find(bytes: &[u8], needle: &[u8]) -> usize {
+    bytes
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .expect("needle should be in the file")
+}
+
+fn inflate_libflate(data: &[u8]) -> Vec<u8> {
+    let mut decoded = Vec::new();
+    libflate::zlib::Decoder::new(data)
+        .and_then(|mut decoder| decoder.read_to_end(&mut decoded))
+        .expect("libflate failed to decode");
+    decoded
+}
+
+fn inflate_flate2(data: &[u8]) -> Vec<u8> {
+    let mut decoded = Vec::new();
+    flate2::read::ZlibDecoder::new(data)
+        .read_to_end(&mut decoded)
+        .expect("flate2 failed to decode");
+    decoded
+}
+
+fn deflate_libflate(data: &[u8]) -> Vec<u8> {
+    let mut encoder = libflate::zlib::Encoder::new(Vec::new()).unwrap();
+    encoder.write_all(data).unwrap();
+    encoder.finish().into_result().unwrap()
+}
+
+fn deflate_flate2(data: &[u8]) -> Vec<u8> {
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::best());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+/// Swap out the compressed tiles of the only layer in a written file.
+fn splice_tiles(file: &[u8], compressed: &[u8]) -> Vec<u8> {
+    let layer = find(file, b"LAYR") + 4;
+    let start = find(file, b"MAIN") + 4;
+    let old_len = u32::from_le_bytes(file[start..start + 4].try_into().unwrap()) as usize;
+    let mut spliced = file[..start].to_vec();
+    spliced.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+    spliced.extend_from_slice(compressed);
+    spliced.extend_from_slice(&file[start + 4 + old_len..]);
+    let layer_len = u32::from_le_bytes(file[layer..layer + 4].try_into().unwrap()) as usize;
+    let new_len = (layer_len + compressed.len() - old_len) as u32;
+    spliced[layer..layer + 4].copy_from_slice(&new_len.to_le_bytes());
+    spliced
+}
+
+#[test]
+fn backends_agree() -> Result<(), Box<dyn std::error::Error>> {
+    let map = TileMap::read(FILE)?;
+    let mut written = Vec::new();
+    map.write(&mut written)?;
+
+    // Whichever backend the crate was built with, both libraries can read what it wrote
+    let start = find(&written, b"MAIN") + 4;
+    let len = u32::from_le_bytes(written[start..start + 4].try_into()?) as usize;
+    let compressed = &written[start + 4..start + 4 + len];
+    let tiles = inflate_libflate(compressed);
+    assert_eq!(tiles, inflate_flate2(compressed));
+    assert_eq!(tiles.len(), 5 * 5 * 2);
+
+    // ...and it can read what both libraries write
+    for recompressed in [deflate_libflate(&tiles), deflate_flate2(&tiles)] {
+        let spliced = splice_tiles(&written, &recompressed);
+        assert_eq!(TileMap::read(spliced.as_slice())?, map);
+    }
+    Ok(())
+}
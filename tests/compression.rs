@@ -0,0 +1,176 @@
+use ct_tilemap::{
+    BlockKind, CompressionLevel, Framing, ReadOptions, ReadScratch, Tile, TileMap, WriteOptions,
+};
+
+mod common;
+use common::FILE;
+
+fn write(map: &TileMap, compression: CompressionLevel) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    map.write_with_options(
+        &mut bytes,
+        &WriteOptions {
+            compression,
+            ..WriteOptions::default()
+        },
+    )
+    .expect("failed to write map");
+    bytes
+}
+
+#[test]
+fn levels() -> Result<(), Box<dyn std::error::Error>> {
+    let mut map = TileMap::read(FILE)?;
+    // Give the compressor something worth compressing
+    map.layers[0].resize(64, 64);
+    for level in [
+        CompressionLevel::None,
+        CompressionLevel::Fast,
+        CompressionLevel::Default,
+        CompressionLevel::Best,
+    ] {
+        let bytes = write(&map, level);
+        assert_eq!(TileMap::read(bytes.as_slice())?, map, "{level:?}");
+    }
+    let none = write(&map, CompressionLevel::None);
+    let fast = write(&map, CompressionLevel::Fast);
+    let best = write(&map, CompressionLevel::Best);
+    assert!(best.len() <= fast.len());
+    assert!(fast.len() < none.len());
+    Ok(())
+}
+
+/// Build a version 5 tilemap with a single 2x2 layer, whose tiles are compressed by `compress`.
+fn single_layer(tiles: &[u8], compress: impl FnOnce(&[u8]) -> Vec<u8>) -> Vec<u8> {
+    let compressed = compress(tiles);
+    let mut layer = Vec::new();
+    layer.extend_from_slice(&1_u16.to_le_bytes()); // Number of layers
+    layer.extend_from_slice(&2_u32.to_le_bytes());
+    layer.extend_from_slice(&2_u32.to_le_bytes()); // Dimensions
+    layer.extend_from_slice(&[16, 0, 16, 0]); // Tile dimensions
+    layer.extend_from_slice(&[0, 0]); // Tileset and collision
+    layer.extend_from_slice(&[0; 16]); // Offset and scroll
+    layer.extend_from_slice(&[0, 0, 1]); // Wrap and visibility
+    layer.extend_from_slice(&1_f32.to_le_bytes()); // Opacity
+    layer.extend_from_slice(&[0xFF; 3]); // Sublayer link
+    layer.push(1); // One data block
+    layer.extend_from_slice(b"MAIN");
+    layer.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+    layer.extend_from_slice(&compressed);
+
+    let mut file = b"ACHTUNG!\x05\x01LAYR".to_vec();
+    file.extend_from_slice(&(layer.len() as u32).to_le_bytes());
+    file.extend_from_slice(&layer);
+    file
+}
+
+#[test]
+fn raw_deflate() -> Result<(), Box<dyn std::error::Error>> {
+    use flate2::{write::DeflateEncoder, Compression};
+    use std::io::Write;
+
+    let tiles = [0x01, 0x01, 0x02, 0x02, 0xFF, 0xFF, 0x04, 0x04];
+    let file = single_layer(&tiles, |tiles| {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(tiles).expect("failed to compress");
+        encoder.finish().expect("failed to compress")
+    });
+    let map = TileMap::read(file.as_slice())?;
+    assert_eq!(map.framing, Framing::RawDeflate);
+    let ids = map.layers[0]
+        .iter()
+        .map(|tile| tile.id())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, [0x0101, 0x0202, 0xFFFF, 0x0404]);
+
+    // The framing is kept when writing the map back
+    let written = map.to_bytes()?;
+    let read = TileMap::read(written.as_slice())?;
+    assert_eq!(read, map);
+    let zlib = TileMap::read(write(&map, CompressionLevel::Default).as_slice())?;
+    assert_eq!(zlib.framing, Framing::RawDeflate);
+
+    let mut bytes = Vec::new();
+    map.write_with_options(
+        &mut bytes,
+        &WriteOptions {
+            framing: Some(Framing::Zlib),
+            ..WriteOptions::default()
+        },
+    )?;
+    let zlib = TileMap::read(bytes.as_slice())?;
+    assert_eq!(zlib.framing, Framing::Zlib);
+    assert_eq!(zlib.layers, map.layers);
+    assert_eq!(TileMap::read(FILE)?.framing, Framing::Zlib);
+    Ok(())
+}
+
+#[test]
+fn shared_scratch() -> Result<(), Box<dyn std::error::Error>> {
+    let expected = TileMap::read(FILE)?;
+    let mut bigger = expected.clone();
+    bigger.layers[0].resize(64, 64);
+    let bigger_bytes = bigger.to_bytes()?;
+
+    let mut scratch = ReadScratch::new();
+    for i in 0..1000 {
+        // Alternate sizes, so the buffers have leftovers to clear
+        if i % 100 == 0 {
+            let map = TileMap::read_with_scratch(bigger_bytes.as_slice(), &mut scratch)?;
+            assert_eq!(map, bigger);
+        }
+        let map = TileMap::read_with_scratch(FILE, &mut scratch)?;
+        assert_eq!(map, expected);
+    }
+    Ok(())
+}
+
+/// Find the compressed bytes that follow a data block ID in a file.
+fn compressed_after<'a>(file: &'a [u8], id: &[u8; 4], extra: usize) -> &'a [u8] {
+    let start = file.windows(4).position(|window| window == id).unwrap() + 4 + extra;
+    let length = u32::from_le_bytes(file[start..start + 4].try_into().unwrap()) as usize;
+    &file[start + 4..start + 4 + length]
+}
+
+#[test]
+fn raw_blocks() -> Result<(), Box<dyn std::error::Error>> {
+    let (map, raw) = TileMap::read_with_raw_blocks(FILE, &ReadOptions::default())?;
+    assert_eq!(map, TileMap::read(FILE)?);
+    assert_eq!(raw.len(), 2);
+    assert_eq!(
+        raw[&(0, BlockKind::Main)],
+        compressed_after(FILE, b"MAIN", 0)
+    );
+    // Sublayer blocks have a cell size and default value before their data
+    assert_eq!(
+        raw[&(0, BlockKind::Data(0))],
+        compressed_after(FILE, b"DATA", 5)
+    );
+    Ok(())
+}
+
+#[test]
+fn uncompressed() -> Result<(), Box<dyn std::error::Error>> {
+    let mut map = TileMap::read(FILE)?;
+    map.layers[0].resize(16, 16);
+    let tiles = (0..16 * 16)
+        .map(|i| Tile::from_id(0x1000 + i))
+        .collect::<Vec<_>>();
+    map.layers[0].paste(0, 0, 16, 16, &tiles)?;
+    let mut bytes = Vec::new();
+    map.write_uncompressed(&mut bytes)?;
+    assert_eq!(bytes, write(&map, CompressionLevel::None));
+    assert_eq!(TileMap::read(bytes.as_slice())?, map);
+
+    // The tiles are stored as they are, in big endian order
+    let raw = tiles
+        .iter()
+        .flat_map(|tile| tile.id().to_be_bytes())
+        .collect::<Vec<_>>();
+    assert!(bytes.windows(raw.len()).any(|window| window == raw));
+    let sublayer = map.layers[0].sublayers[0].data();
+    assert!(bytes
+        .windows(sublayer.len())
+        .any(|window| window == sublayer));
+    Ok(())
+}
@@ -0,0 +1,98 @@
+use ct_tilemap::{Layer, LayerDiff, Property, Tile, TileDiff, TileMap};
+
+mod common;
+use common::FILE;
+
+#[test]
+fn single_tile() -> Result<(), Box<dyn std::error::Error>> {
+    let old = TileMap::read(FILE)?;
+    assert!(old.diff(&old).is_empty());
+
+    let mut new = old.clone();
+    let before = new.layers[0][(3, 1)];
+    new.layers[0][(3, 1)] = Tile::from_tile_coords(1, 2);
+    let diff = old.diff(&new);
+    assert_eq!(
+        diff.modified_layers,
+        [LayerDiff {
+            old_index: 0,
+            new_index: 0,
+            tiles: TileDiff::Changed(vec![(3, 1, before, Tile::from_tile_coords(1, 2))]),
+            metadata_changed: false,
+        }]
+    );
+    assert!(diff.added_layers.is_empty() && diff.removed_layers.is_empty());
+    assert!(diff.properties.is_empty());
+
+    // Only the settings changed
+    let mut new = old.clone();
+    new.layers[0].visible = !new.layers[0].visible;
+    let diff = old.diff(&new);
+    assert_eq!(diff.modified_layers[0].tiles, TileDiff::Changed(Vec::new()));
+    assert!(diff.modified_layers[0].metadata_changed);
+    Ok(())
+}
+
+#[test]
+fn resized_layer() -> Result<(), Box<dyn std::error::Error>> {
+    let old = TileMap::read(FILE)?;
+    let mut new = old.clone();
+    new.layers[0].resize(6, 5);
+    let diff = old.diff(&new);
+    assert_eq!(diff.modified_layers.len(), 1);
+    assert_eq!(
+        diff.modified_layers[0].tiles,
+        TileDiff::ShapeChanged((5, 5), (6, 5))
+    );
+    // The sublayers were resized along with the layer
+    assert!(diff.modified_layers[0].metadata_changed);
+    Ok(())
+}
+
+#[test]
+fn layers_and_properties() -> Result<(), Box<dyn std::error::Error>> {
+    let old = TileMap::read(FILE)?;
+    let mut new = old.clone();
+    let inserted = Layer::builder().size(2, 2).tileset(1).build();
+    new.layers.insert(0, inserted.clone());
+    new.layers.push(inserted);
+    new.set_int("Integer", 197);
+    new.remove_property("Float");
+    new.set_string("Name", "Level 1");
+
+    // The original layer is matched up, even though its index changed
+    let diff = old.diff(&new);
+    assert_eq!(diff.added_layers, [0, 2]);
+    assert!(diff.removed_layers.is_empty());
+    assert!(diff.modified_layers.is_empty());
+    assert_eq!(
+        diff.properties["Integer"],
+        (Some(Property::Integer(196)), Some(Property::Integer(197)))
+    );
+    assert_eq!(diff.properties["Float"], (Some(Property::Float(2.2)), None));
+    assert_eq!(
+        diff.properties["Name"],
+        (None, Some(Property::String(b"Level 1".to_vec())))
+    );
+    assert_eq!(diff.properties.len(), 3);
+
+    let reverse = new.diff(&old);
+    assert_eq!(reverse.removed_layers, [0, 2]);
+    assert!(reverse.added_layers.is_empty());
+
+    // Layers left over between matches are paired up as modified
+    let mut replaced = old.clone();
+    replaced.layers[0].tileset = 7;
+    replaced.layers.push(Layer::new());
+    let diff = old.diff(&replaced);
+    assert_eq!(diff.modified_layers.len(), 1);
+    assert_eq!(
+        (
+            diff.modified_layers[0].old_index,
+            diff.modified_layers[0].new_index
+        ),
+        (0, 0)
+    );
+    assert_eq!(diff.added_layers, [1]);
+    Ok(())
+}
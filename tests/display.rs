@@ -0,0 +1,65 @@
+use ct_tilemap::{Layer, TileMap, TileMapStats};
+
+mod common;
+use common::FILE;
+
+#[test]
+fn summaries() -> Result<(), Box<dyn std::error::Error>> {
+    let mut map = TileMap::read(FILE)?;
+    assert_eq!(
+        map.to_string(),
+        "TileMap(1 layer, 2 tilesets, 3 properties, 5x5)"
+    );
+    assert_eq!(
+        map.layers[0].to_string(),
+        "Layer 5x5, tileset 255, 1 sublayer"
+    );
+    assert_eq!(map.tilesets[0].to_string(), "overworld.png #7289DA");
+
+    let mut big = Layer::new();
+    big.resize(40, 30);
+    map.layers.push(big);
+    map.properties.clear();
+    assert_eq!(
+        map.to_string(),
+        "TileMap(2 layers, 2 tilesets, 0 properties, 40x30)"
+    );
+    assert_eq!(
+        TileMap::new().to_string(),
+        "TileMap(0 layers, 0 tilesets, 0 properties, 0x0)"
+    );
+    Ok(())
+}
+
+#[test]
+fn stats() -> Result<(), Box<dyn std::error::Error>> {
+    let mut map = TileMap::read(FILE)?;
+    assert_eq!(
+        map.stats(),
+        TileMapStats {
+            layers: 1,
+            tilesets: 2,
+            properties: 3,
+            tiles: 25,
+            non_empty_tiles: 9,
+            sublayer_bytes: 25,
+            bounds: (5, 5),
+        }
+    );
+    assert_eq!(
+        map.stats().to_string(),
+        "1 layer, 2 tilesets, 3 properties, 25 tiles (9 non-empty), 25 sublayer bytes, bounds 5x5"
+    );
+
+    let mut wide = Layer::new();
+    wide.resize(40, 2);
+    map.layers.push(wide);
+    let stats = map.stats();
+    assert_eq!((stats.layers, stats.tiles, stats.bounds), (2, 105, (40, 5)));
+    assert_eq!(stats.non_empty_tiles, 9);
+    assert_eq!(
+        TileMap::new().stats().to_string(),
+        "0 layers, 0 tilesets, 0 properties, 0 tiles (0 non-empty), 0 sublayer bytes, bounds 0x0"
+    );
+    Ok(())
+}
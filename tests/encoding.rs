@@ -0,0 +1,44 @@
+#![cfg(feature = "encoding_rs")]
+
+use const_str::concat_bytes;
+use ct_tilemap::encoding_rs::WINDOWS_1252;
+use ct_tilemap::{ReadOptions, TileMap, WriteOptions};
+
+const CURLY_QUOTE: &[u8] = concat_bytes!(
+    b"ACHTUNG!",          // Magic string
+    b"\x05\x01",          // Version 5
+    b"TILE",              // Tilesets
+    14_u32.to_le_bytes(), // Block length
+    1,                    // Number of tilesets
+    b"\x00\xda\x89\x72",  // Transparent color
+    7,
+    b"It\x92s.png", // Path, in Windows-1252
+);
+
+#[test]
+fn windows_1252() -> Result<(), Box<dyn std::error::Error>> {
+    let lossy = TileMap::read(CURLY_QUOTE)?;
+    assert_eq!(lossy.tilesets[0].path, "It\u{FFFD}s.png");
+
+    let options = ReadOptions {
+        encoding: Some(WINDOWS_1252),
+        ..ReadOptions::default()
+    };
+    let mut map = TileMap::read_with_options(CURLY_QUOTE, &options)?;
+    assert_eq!(map.tilesets[0].path, "It\u{2019}s.png");
+
+    let options = WriteOptions {
+        encoding: Some(WINDOWS_1252),
+        ..WriteOptions::default()
+    };
+    map.properties.insert("Caf\u{E9}".into(), 1.into());
+    let mut bytes = Vec::new();
+    map.write_with_options(&mut bytes, &options)?;
+    assert!(bytes.windows(8).any(|window| window == b"It\x92s.png"));
+    assert!(bytes.windows(4).any(|window| window == b"Caf\xE9"));
+
+    // UTF-8 is still the default when writing
+    let utf8 = TileMap::from_bytes(&map.to_bytes()?)?;
+    assert_eq!(utf8.tilesets[0].path, "It\u{2019}s.png");
+    Ok(())
+}
@@ -0,0 +1,138 @@
+#![cfg(feature = "std")]
+
+use ct_tilemap::{Layer, Property, Tile, TileMap, TileSet, WriteOptions};
+use std::io::{Cursor, Seek, SeekFrom};
+
+mod common;
+use common::FILE;
+
+#[test]
+fn round_trip_through_file() -> Result<(), Box<dyn std::error::Error>> {
+    let map = TileMap::read(FILE)?;
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("map.map");
+    map.write_file(&path)?;
+    assert_eq!(TileMap::read_file(&path)?, map);
+    assert!(TileMap::read_file(dir.path().join("missing.map")).is_err());
+    Ok(())
+}
+
+#[test]
+fn write_into_vec() -> Result<(), Box<dyn std::error::Error>> {
+    let map = TileMap::read(FILE)?;
+    let expected = map.to_bytes()?;
+
+    // Anything already in the buffer is kept
+    let mut buf = b"prefix".to_vec();
+    map.write_into(&mut buf)?;
+    assert_eq!(&buf[..6], b"prefix");
+    assert_eq!(&buf[6..], expected);
+
+    let mut written = Vec::new();
+    map.write(&mut written)?;
+    assert_eq!(written, expected);
+    Ok(())
+}
+
+#[test]
+fn write_to_slice() -> Result<(), Box<dyn std::error::Error>> {
+    let map = TileMap::read(FILE)?;
+    let expected = map.to_bytes()?;
+    let mut buf = vec![0xAA; expected.len() + 8];
+    let written = map.write_to_slice(&mut buf)?;
+    assert_eq!(written, expected.len());
+    assert_eq!(&buf[..written], expected);
+    // Anything past the tilemap is left alone
+    assert!(buf[written..].iter().all(|&byte| byte == 0xAA));
+    assert_eq!(TileMap::from_bytes(&buf[..written])?, map);
+
+    let err = map
+        .write_to_slice(&mut buf[..expected.len() - 1])
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::WriteZero);
+    Ok(())
+}
+
+#[test]
+fn seekable_write() -> Result<(), Box<dyn std::error::Error>> {
+    let mut map = TileMap::read(FILE)?;
+    map.unknown_blocks.push((*b"EXT1", b"extra".to_vec()));
+    let options = WriteOptions::default();
+    let mut buffered = Vec::new();
+    map.write_with_options(&mut buffered, &options)?;
+
+    let mut seeked = Cursor::new(Vec::new());
+    map.write_seekable(&mut seeked, &options)?;
+    assert_eq!(seeked.get_ref(), &buffered);
+
+    // Writing after existing data keeps it, and fills in lengths relative to the stream
+    let mut offset = Cursor::new(b"prefix".to_vec());
+    offset.seek(SeekFrom::End(0))?;
+    map.write_seekable(&mut offset, &options)?;
+    assert_eq!(&offset.get_ref()[6..], buffered);
+    assert_eq!(TileMap::from_bytes(&offset.get_ref()[6..])?, map);
+    Ok(())
+}
+
+#[test]
+fn large_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+    let mut map = TileMap::new();
+    for i in 0..1000 {
+        map.properties
+            .insert(format!("Property {i}"), Property::Integer(i));
+    }
+    for i in 0..200 {
+        map.tilesets.push(TileSet {
+            path: format!("tileset_{i}.png"),
+            transparent_color: (i, 0, 0),
+        });
+    }
+    for i in 0..100 {
+        let mut layer = Layer::new();
+        layer.resize(32, 16);
+        for (j, tile) in layer.iter_mut().enumerate() {
+            *tile = Tile::from_id((i * 7 + j) as u16);
+        }
+        if i % 3 == 0 {
+            layer.add_sublayer(&[1, 2]);
+        }
+        map.layers.push(layer);
+    }
+    let bytes = map.to_bytes()?;
+    let read = TileMap::from_bytes(&bytes)?;
+    assert_eq!(read, map);
+
+    let header = TileMap::read_header_only(Cursor::new(&bytes))?;
+    assert_eq!(header.layers.len(), 100);
+    assert_eq!(header.tilesets, map.tilesets);
+    assert_eq!(header.properties, map.properties);
+    Ok(())
+}
+
+#[test]
+fn try_from_bytes() -> Result<(), Box<dyn std::error::Error>> {
+    let map = TileMap::try_from(FILE)?;
+    assert_eq!(map, TileMap::from_bytes(FILE)?);
+    let bytes: Vec<u8> = (&map).try_into()?;
+    assert_eq!(bytes, map.to_bytes()?);
+    assert!(TileMap::try_from(b"Not a tilemap".as_slice()).is_err());
+    Ok(())
+}
+
+#[test]
+fn empty_map() -> Result<(), Box<dyn std::error::Error>> {
+    let map = TileMap::default();
+    assert!(map.is_empty());
+    // Only the magic string and version are written
+    let bytes = map.to_bytes()?;
+    assert_eq!(bytes, b"ACHTUNG!\x05\x01");
+    let read = TileMap::from_bytes(&bytes)?;
+    assert_eq!(read, map);
+    assert!(read.is_empty());
+
+    assert!(!TileMap::read(FILE)?.is_empty());
+    let mut unknown = TileMap::new();
+    unknown.unknown_blocks.push((*b"EXT1", Vec::new()));
+    assert!(!unknown.is_empty());
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use ct_tilemap::{ReadError, TileMap, TileMapReader};
+use std::io::Cursor;
+
+mod common;
+use common::FILE;
+
+#[test]
+fn lazy_layers() -> Result<(), ReadError> {
+    let map = TileMap::read(FILE)?;
+    let header = TileMap::read_header_only(Cursor::new(FILE))?;
+    assert_eq!(header.version, map.version);
+    assert_eq!(header.properties, map.properties);
+    assert_eq!(header.tilesets, map.tilesets);
+    assert_eq!(header.layers.len(), 1);
+    let layer_header = &header.layers[0];
+    assert_eq!((layer_header.width, layer_header.height), (5, 5));
+    assert_eq!(layer_header.opacity, map.layers[0].opacity);
+    assert_eq!(layer_header.sublayer_link, map.layers[0].sublayer_link);
+
+    let layer = header.load_layer(Cursor::new(FILE), 0)?;
+    assert_eq!(layer, map.layers[0]);
+
+    // Tilemaps don't have to start at the beginning of the stream
+    let mut padded = b"padding".to_vec();
+    padded.extend_from_slice(FILE);
+    let mut cursor = Cursor::new(padded.as_slice());
+    cursor.set_position(7);
+    let header = TileMap::read_header_only(&mut cursor)?;
+    assert_eq!(header.load_layer(&mut cursor, 0)?, map.layers[0]);
+
+    // Truncated data is noticed, even though it's never decoded
+    let truncated = &FILE[..FILE.len() - 4];
+    assert!(TileMap::read_header_only(Cursor::new(truncated)).is_err());
+    Ok(())
+}
+
+#[test]
+fn reader() -> Result<(), ReadError> {
+    let map = TileMap::read(FILE)?;
+    let mut reader = TileMapReader::new(Cursor::new(FILE))?;
+    assert_eq!(reader.layer_count(), 1);
+    assert_eq!(reader.header().tilesets, map.tilesets);
+    let meta = reader.layer_meta(0).unwrap();
+    assert_eq!((meta.width, meta.height), (5, 5));
+    assert!(reader.layer_meta(1).is_none());
+
+    // Layers can be read any number of times
+    assert_eq!(reader.read_layer(0)?, map.layers[0]);
+    assert_eq!(reader.read_layer(0)?, map.layers[0]);
+    assert_eq!(reader.into_inner().into_inner(), FILE);
+    Ok(())
+}
@@ -1,10 +1,12 @@
-#![feature(const_float_bits_conv)]
-
 use const_str::concat_bytes;
-use ct_tilemap::{ReadError, TileMap};
+use ct_tilemap::{ReadError, ReadOptions, TileMap};
+use std::error::Error;
 use std::io;
 use std::io::Read;
 
+mod common;
+use common::FILE;
+
 struct ErrorsAtEnd(usize, &'static [u8]);
 impl Read for ErrorsAtEnd {
     fn read(&mut self, b: &mut [u8]) -> io::Result<usize> {
@@ -42,6 +44,18 @@ const INVALID_MAPPING: &[u8] = concat_bytes!(
     b"Invalid property"
 );
 
+const WRONG_BLOCK_SIZE: &[u8] = concat_bytes!(
+    b"ACHTUNG!",          // Magic string
+    b"\x05\x01",          // Version 5
+    b"MAP ",              // Property mapping
+    10_u32.to_le_bytes(), // Block length, should be 13
+    1_u16.to_le_bytes(),  // Number of properties
+    4,
+    b"Hello", // Property 1
+    0,
+    1_i32.to_le_bytes(), // Integer
+);
+
 const INVALID_LAYER_SIZE: &[u8] = concat_bytes!(
     b"ACHTUNG!",           // Magic string
     b"\x05\x01",           // Version 5
@@ -168,28 +182,218 @@ fn invalid_files() {
         dbg!(TileMap::read(UNSUPPORTED_VERSION)).unwrap_err(),
         ReadError::UnsupportedVersion(9)
     ));
+    let strict = ReadOptions {
+        strict: true,
+        ..ReadOptions::default()
+    };
     assert!(matches!(
-        dbg!(TileMap::read(INVALID_HEADER)).unwrap_err(),
-        ReadError::InvalidHeader(_)
+        dbg!(TileMap::read_with_options(INVALID_HEADER, &strict)).unwrap_err(),
+        ReadError::InvalidHeader { offset: 10, .. }
     ));
     assert!(matches!(
         dbg!(TileMap::read(INVALID_LAYER_HEADER)).unwrap_err(),
-        ReadError::InvalidHeader(_)
-    ));
-    assert!(matches!(
-        dbg!(TileMap::read(INVALID_MAPPING)).unwrap_err(),
-        ReadError::InvalidType(9)
+        ReadError::InvalidHeader { offset: 61, .. }
     ));
+    // Points at the type byte, after the magic string, version, block header, count and name
+    let err = dbg!(TileMap::read(INVALID_MAPPING)).unwrap_err();
+    assert!(matches!(err, ReadError::InvalidType { ty: 9, offset: 28 }));
+    assert_eq!(err.offset(), Some(28));
     assert!(matches!(
         dbg!(TileMap::read(INVALID_LAYER_SIZE)).unwrap_err(),
-        ReadError::InvalidLayerLength
+        ReadError::InvalidLayerLength { offset: 61 }
     ));
     assert!(matches!(
         dbg!(TileMap::read(INVALID_COMPRESSED)).unwrap_err(),
-        ReadError::IoError(_)
+        ReadError::Decompression(_)
+    ));
+    assert_eq!(TileMap::read(WRONG_STRING).unwrap_err().offset(), Some(0));
+    assert!(TileMap::read(WRONG_BLOCK_SIZE).is_ok());
+    let validated = ReadOptions {
+        validate_block_sizes: true,
+        ..ReadOptions::default()
+    };
+    assert!(matches!(
+        dbg!(TileMap::read_with_options(WRONG_BLOCK_SIZE, &validated)).unwrap_err(),
+        ReadError::BlockSizeMismatch {
+            block: [b'M', b'A', b'P', b' '],
+            expected: 10,
+            actual: 13,
+            offset: 10
+        }
     ));
     assert!(matches!(
         dbg!(TileMap::read(ErrorsAtEnd(0, b"ACHTUNG!\x05\x01"))).unwrap_err(),
         ReadError::IoError(_)
     ));
 }
+
+#[test]
+fn trailing_bytes() -> Result<(), ReadError> {
+    let strict = ReadOptions {
+        strict: true,
+        ..ReadOptions::default()
+    };
+    let padded = [FILE, b"\0\0\0"].concat();
+    assert_eq!(TileMap::read(padded.as_slice())?, TileMap::read(FILE)?);
+    let offset = FILE.len() as u64;
+    assert!(matches!(
+        dbg!(TileMap::read_with_options(padded.as_slice(), &strict)).unwrap_err(),
+        ReadError::InvalidHeader { offset: o, .. } if o == offset
+    ));
+    // Anything long enough to be a block header is still read as one
+    let padded = [FILE, b"\0\0\0\0\0\0\0\0"].concat();
+    assert!(TileMap::read_with_options(padded.as_slice(), &strict).is_err());
+    Ok(())
+}
+
+#[test]
+fn decompression_bomb() -> Result<(), Box<dyn std::error::Error>> {
+    // 8 MiB of tiles that compress down to almost nothing
+    let mut map = TileMap::new();
+    let mut layer = ct_tilemap::Layer::new();
+    layer.resize(2048, 2048);
+    map.layers.push(layer);
+    let bomb = map.to_bytes()?;
+    assert!(bomb.len() < 64 * 1024);
+
+    let limited = ReadOptions {
+        max_decompressed_size: 1024 * 1024,
+        ..ReadOptions::default()
+    };
+    let err = TileMap::read_with_options(bomb.as_slice(), &limited).unwrap_err();
+    assert!(matches!(
+        err,
+        ReadError::DecompressedTooLarge {
+            limit: 0x10_0000,
+            ..
+        }
+    ));
+    assert!(err.offset().is_some());
+    // Exactly at the limit is fine
+    let exact = ReadOptions {
+        max_decompressed_size: 2048 * 2048 * 2,
+        ..ReadOptions::default()
+    };
+    assert_eq!(TileMap::read_with_options(bomb.as_slice(), &exact)?, map);
+    Ok(())
+}
+
+#[test]
+fn error_sources() {
+    let truncated = TileMap::read(ErrorsAtEnd(0, b"ACHTUNG!\x05\x01")).unwrap_err();
+    let source = truncated.source().expect("IO errors should have a source");
+    assert_eq!(source.to_string(), "oh no!");
+
+    let corrupt = TileMap::read(INVALID_COMPRESSED).unwrap_err();
+    assert!(corrupt.source().is_some());
+    assert!(TileMap::read(WRONG_STRING).unwrap_err().source().is_none());
+}
+
+#[test]
+fn truncated_block() -> Result<(), Box<dyn std::error::Error>> {
+    let mut map = TileMap::new();
+    map.layers.push(ct_tilemap::Layer::new());
+    let mut bytes = map.to_bytes()?;
+    // Claim 60000 layers, with only one in the file
+    let block = bytes
+        .windows(4)
+        .position(|window| window == b"LAYR")
+        .unwrap();
+    bytes[block + 8..block + 10].copy_from_slice(&60000_u16.to_le_bytes());
+
+    let err = TileMap::from_bytes(&bytes).unwrap_err();
+    assert!(matches!(
+        err,
+        ReadError::TruncatedBlock {
+            block: [b'L', b'A', b'Y', b'R'],
+            offset
+        } if offset == block as u64
+    ));
+    assert!(matches!(
+        TileMap::read_header_only(io::Cursor::new(&bytes)).unwrap_err(),
+        ReadError::TruncatedBlock { .. }
+    ));
+    // Without knowing the length, this only fails once the bytes run out
+    assert!(matches!(
+        TileMap::read(bytes.as_slice()).unwrap_err(),
+        ReadError::UnexpectedEof {
+            block: Some([b'L', b'A', b'Y', b'R']),
+            offset
+        } if offset == block as u64
+    ));
+
+    // The real count still reads fine
+    bytes[block + 8..block + 10].copy_from_slice(&1_u16.to_le_bytes());
+    assert_eq!(TileMap::from_bytes(&bytes)?, map);
+    Ok(())
+}
+
+#[test]
+fn unexpected_eof() {
+    let layers = FILE.windows(4).position(|id| id == b"LAYR").unwrap();
+    // Cut off partway through the first layer's header
+    let truncated = &FILE[..layers + 30];
+    let err = TileMap::read(truncated).unwrap_err();
+    assert!(matches!(
+        err,
+        ReadError::UnexpectedEof {
+            block: Some([b'L', b'A', b'Y', b'R']),
+            offset
+        } if offset == layers as u64
+    ));
+    assert_eq!(
+        err.to_string(),
+        format!("file ended partway through block \"LAYR\" at offset {layers}")
+    );
+    // Cut off partway through the compressed tile data
+    assert!(matches!(
+        TileMap::read(&FILE[..FILE.len() - 40]).unwrap_err(),
+        ReadError::UnexpectedEof {
+            block: Some([b'L', b'A', b'Y', b'R']),
+            ..
+        }
+    ));
+
+    assert!(matches!(
+        TileMap::read(&FILE[..9]).unwrap_err(),
+        ReadError::UnexpectedEof {
+            block: None,
+            offset: 0
+        }
+    ));
+    // Ending cleanly between blocks is fine
+    assert!(TileMap::read(&FILE[..layers]).is_ok());
+}
+
+#[test]
+fn mismatched_tile_count() {
+    let layers = FILE.windows(4).position(|id| id == b"LAYR").unwrap();
+    let main = FILE.windows(4).position(|id| id == b"MAIN").unwrap();
+    // Claim a 6x6 layer, while the tile data only holds 5x5 tiles
+    let mut bytes = FILE.to_vec();
+    bytes[layers + 10..layers + 14].copy_from_slice(&6_u32.to_le_bytes());
+    bytes[layers + 14..layers + 18].copy_from_slice(&6_u32.to_le_bytes());
+    let err = TileMap::from_bytes(&bytes).unwrap_err();
+    assert!(matches!(
+        err,
+        ReadError::InvalidLayerLength { offset } if offset == main as u64
+    ));
+}
+
+#[test]
+fn missing_tiles() {
+    let layers = FILE.windows(4).position(|id| id == b"LAYR").unwrap();
+    let data_count = FILE.windows(4).position(|id| id == b"MAIN").unwrap() - 1;
+    // Claim there are no data blocks, so the 5x5 layer has no tiles
+    let mut bytes = FILE.to_vec();
+    bytes[data_count] = 0;
+    let block_size = u32::from_le_bytes(bytes[layers + 4..layers + 8].try_into().unwrap());
+    let removed = bytes.len() - data_count - 1;
+    bytes.truncate(data_count + 1);
+    bytes[layers + 4..layers + 8].copy_from_slice(&(block_size - removed as u32).to_le_bytes());
+    let err = TileMap::from_bytes(&bytes).unwrap_err();
+    assert!(matches!(
+        err,
+        ReadError::InvalidLayerLength { offset } if offset == data_count as u64
+    ));
+}
@@ -1,4 +1,4 @@
-use ct_tilemap::TileMap;
+use ct_tilemap::{Layer, LimitKind, TileMap, TileSet, WriteError, WriteOptions};
 use std::io::{Cursor, Write};
 
 struct TrashWriter;
@@ -43,3 +43,66 @@ fn invalid_writes() {
         256
     );
 }
+
+#[test]
+fn strict_strings() {
+    let mut map = TileMap::default();
+    map.tilesets.push(TileSet::new());
+    map.tilesets.push(TileSet {
+        path: "a".repeat(300),
+        ..TileSet::default()
+    });
+    map.tilesets[0].path = "short.png".into();
+    let strict = WriteOptions {
+        strict_strings: true,
+        ..WriteOptions::default()
+    };
+    assert!(matches!(
+        map.write_with_options(TrashWriter, &strict),
+        Err(WriteError::TilesetPathTooLong(1))
+    ));
+
+    let mut cur = Cursor::new(Vec::new());
+    map.write(&mut cur).expect("this doesn't fail");
+    let read = TileMap::read(cur.get_ref().as_slice()).expect("reading should not fail here");
+    assert_eq!(read.tilesets[1].path, "a".repeat(256));
+
+    map.tilesets.pop();
+    map.properties.insert("b".repeat(257), 0.into());
+    assert!(matches!(
+        map.write_with_options(TrashWriter, &strict),
+        Err(WriteError::PropertyKeyTooLong(key)) if key.len() == 257
+    ));
+}
+
+#[test]
+fn limits() {
+    let mut map = TileMap::new();
+    map.tilesets = vec![TileSet::new(); 256];
+    assert!(matches!(
+        map.write_checked(TrashWriter),
+        Err(WriteError::LimitExceeded {
+            kind: LimitKind::Tilesets,
+            count: 256,
+            max: 255
+        })
+    ));
+    // The lossy writer leaves the last one out instead
+    map.tilesets
+        .iter_mut()
+        .for_each(|tileset| tileset.path = "a".into());
+    map.write(TrashWriter).expect("this doesn't fail");
+
+    let mut map = TileMap::new();
+    map.layers = vec![Layer::new(); 65537];
+    assert!(matches!(
+        map.write_checked(TrashWriter),
+        Err(WriteError::LimitExceeded {
+            kind: LimitKind::Layers,
+            count: 65537,
+            max: 65535
+        })
+    ));
+    map.layers.truncate(65535);
+    map.write_checked(TrashWriter).expect("this doesn't fail");
+}
@@ -0,0 +1,880 @@
+use ct_tilemap::{
+    Anchor, AreaOverflow, Layer, LayerBuilder, LayerShapeError, PasteError, ReadError, ReadOptions,
+    SubLayer, Tile, TileMap, WriteError, WriteOptions,
+};
+
+mod common;
+use common::FILE;
+
+#[test]
+fn iter_coords() {
+    let mut layer = Layer::new();
+    assert_eq!(layer.iter_coords().count(), 0);
+    layer.resize(8, 8);
+    for ((x, y), tile) in layer.iter_coords_mut() {
+        *tile = Tile {
+            id: (y * 8 + x) as u16,
+        };
+    }
+    let coords = layer.iter_coords().collect::<Vec<_>>();
+    assert_eq!(coords.len(), 64);
+    assert_eq!(coords[9].0, (1, 1));
+    let &((x, y), tile) = coords.last().unwrap();
+    assert_eq!((x, y), (7, 7));
+    assert_eq!(tile, &layer[(x, y)]);
+    assert_eq!(tile.id(), 63);
+}
+
+#[test]
+fn flip() {
+    let mut layer = Layer::new();
+    layer.resize(3, 2);
+    for (i, tile) in layer.iter_mut().enumerate() {
+        *tile = Tile { id: i as u16 };
+    }
+    let sublayer = layer.add_sublayer(b"\0\0\0");
+    for (i, cell) in sublayer.iter_mut().enumerate() {
+        cell.copy_from_slice(&[i as u8, 0x10, 0x20]);
+    }
+    let original = layer.clone();
+
+    layer.flip_horizontal();
+    assert_eq!(layer[(0, 0)].id(), 2);
+    assert_eq!(layer[(2, 1)].id(), 3);
+    assert_eq!(&layer.sublayers[0][(0, 0)], &[2, 0x10, 0x20]);
+    assert_eq!(&layer.sublayers[0][(2, 1)], &[3, 0x10, 0x20]);
+    layer.flip_horizontal();
+    assert_eq!(layer, original);
+
+    layer.flip_vertical();
+    assert_eq!(layer[(0, 0)].id(), 3);
+    assert_eq!(layer[(2, 1)].id(), 2);
+    assert_eq!(&layer.sublayers[0][(0, 0)], &[3, 0x10, 0x20]);
+    assert_eq!(&layer.sublayers[0][(2, 1)], &[2, 0x10, 0x20]);
+    layer.flip_vertical();
+    assert_eq!(layer, original);
+
+    // Position tiles aren't reinterpreted
+    layer[(0, 0)] = Tile { position: [5, 3] };
+    layer.flip_horizontal();
+    assert_eq!(layer[(2, 0)].position(), [5, 3]);
+}
+
+#[test]
+fn rotate() {
+    let mut layer = Layer::new();
+    layer.resize(2, 3);
+    layer.tile_dimensions = (8, 16);
+    for (i, tile) in layer.iter_mut().enumerate() {
+        *tile = Tile { id: i as u16 };
+    }
+    let sublayer = layer.add_sublayer(b"\0\0\0");
+    for (i, cell) in sublayer.iter_mut().enumerate() {
+        cell.copy_from_slice(&[i as u8, 0x10, 0x20]);
+    }
+    let original = layer.clone();
+
+    // 0 1     4 2 0
+    // 2 3  -> 5 3 1
+    // 4 5
+    layer.rotate_cw();
+    assert_eq!((layer.width(), layer.height()), (3, 2));
+    assert_eq!(layer.tile_dimensions, (16, 8));
+    assert_eq!(layer[(0, 0)].id(), 4);
+    assert_eq!(layer[(2, 1)].id(), 1);
+    assert_eq!(&layer.sublayers[0][(0, 0)], &[4, 0x10, 0x20]);
+    assert_eq!(layer.sublayers[0].width(), 3);
+    for _ in 0..3 {
+        layer.rotate_cw();
+    }
+    assert_eq!(layer, original);
+
+    // 0 1     1 3 5
+    // 2 3  -> 0 2 4
+    // 4 5
+    layer.rotate_ccw();
+    assert_eq!(layer[(0, 0)].id(), 1);
+    assert_eq!(layer[(2, 1)].id(), 4);
+    assert_eq!(&layer.sublayers[0][(2, 1)], &[4, 0x10, 0x20]);
+    layer.rotate_cw();
+    assert_eq!(layer, original);
+
+    let mut empty = Layer::new();
+    empty.rotate_cw();
+    empty.rotate_ccw();
+    assert_eq!(empty, Layer::new());
+}
+
+#[test]
+fn crop() -> Result<(), ReadError> {
+    let map = TileMap::read(FILE)?;
+    let layer = &map.layers[0];
+    let cropped = layer.crop(1, 2, 2, 2).expect("region is in bounds");
+    assert_eq!((cropped.width(), cropped.height()), (2, 2));
+    for ((x, y), tile) in cropped.iter_coords() {
+        assert_eq!(tile, &layer[(x + 1, y + 2)]);
+    }
+    let sublayer = &layer.sublayers[0];
+    let cropped_sublayer = &cropped.sublayers[0];
+    assert_eq!(cropped_sublayer.cell_size(), sublayer.cell_size());
+    for y in 0..2 {
+        for x in 0..2 {
+            assert_eq!(&cropped_sublayer[(x, y)], &sublayer[(x + 1, y + 2)]);
+        }
+    }
+    assert_eq!(cropped.opacity, layer.opacity);
+    assert_eq!(cropped.tile_dimensions, layer.tile_dimensions);
+    assert_eq!(cropped.sublayer_link, layer.sublayer_link);
+
+    assert_eq!(layer.crop(0, 0, 5, 5).as_ref(), Some(layer));
+    assert!(layer.crop(0, 0, 0, 0).is_some());
+    assert!(layer.crop(4, 0, 2, 1).is_none());
+    assert!(layer.crop(0, 5, 1, 1).is_none());
+    assert!(layer.crop(u32::MAX, 0, 2, 1).is_none());
+    Ok(())
+}
+
+#[test]
+fn fill() {
+    let mut layer = Layer::new();
+    layer.resize(8, 8);
+    layer.fill_rect(0, 0, 4, 4, Tile { id: 0x1234 });
+    for ((x, y), tile) in layer.iter_coords() {
+        if x < 4 && y < 4 {
+            assert_eq!(tile.id(), 0x1234);
+        } else {
+            assert_eq!(tile.id(), 0xFFFF);
+        }
+    }
+    // Clamped to bounds
+    layer.fill_rect(6, 6, u32::MAX, u32::MAX, Tile { id: 0 });
+    assert_eq!(layer[(7, 7)].id(), 0);
+    assert_eq!(layer[(5, 7)].id(), 0xFFFF);
+    layer.fill_rect(9, 9, 2, 2, Tile { id: 0 });
+    layer.clear();
+    assert!(layer.iter().all(|tile| tile.id() == 0xFFFF));
+    assert_eq!(layer.iter().count(), 64);
+}
+
+#[test]
+fn coordinate_types() -> Result<(), ReadError> {
+    let mut map = TileMap::read(FILE)?;
+    let layer = &mut map.layers[0];
+    for ((x, y), tile) in layer.clone().iter_coords() {
+        assert_eq!(layer.tile((x as u32, y as u32)), Some(tile));
+        assert_eq!(&layer[(x, y)], tile);
+    }
+    assert_eq!(layer.tile((5, 0)), None);
+    assert_eq!(layer.get((5, 0)), None);
+    assert_eq!(layer.tile((0, 5)), None);
+    *layer.tile_mut((1, 2)).expect("tile is in bounds") = Tile::from_id(0x1234);
+    assert_eq!(layer[(1, 2)].id(), 0x1234);
+    assert_eq!(
+        layer.sublayers[0].get((1, 2)),
+        Some(&layer.sublayers[0][(1, 2)])
+    );
+    Ok(())
+}
+
+#[test]
+fn blit() {
+    let mut layer = Layer::new();
+    layer.resize(8, 8);
+    layer.fill_rect(0, 0, 8, 8, Tile::from_id(1));
+    layer.add_sublayer(b"\0\0");
+    layer.add_sublayer(b"\0");
+
+    let mut stamp = Layer::new();
+    stamp.resize(2, 2);
+    stamp[(0, 0)] = Tile::from_id(2);
+    stamp[(1, 1)] = Tile::from_id(3);
+    let sublayer = stamp.add_sublayer(b"\0\0");
+    sublayer[(0, 0)].copy_from_slice(b"AB");
+    sublayer[(1, 1)].copy_from_slice(b"CD");
+    // Doesn't match the cell size of the second sublayer
+    stamp.add_sublayer(b"\0\0");
+
+    let mut skipping = layer.clone();
+    skipping.blit(&stamp, 6, 6, true);
+    assert_eq!(skipping[(6, 6)].id(), 2);
+    assert_eq!(skipping[(7, 6)].id(), 1);
+    assert_eq!(skipping[(6, 7)].id(), 1);
+    assert_eq!(skipping[(7, 7)].id(), 3);
+    assert_eq!(&skipping.sublayers[0][(6, 6)], b"AB");
+    assert_eq!(&skipping.sublayers[0][(7, 7)], b"CD");
+    assert_eq!(skipping.sublayers[1], layer.sublayers[1]);
+
+    // Clipped at the edges
+    let mut clipped = layer.clone();
+    clipped.blit(&stamp, 7, 7, false);
+    assert_eq!(clipped[(7, 7)].id(), 2);
+    assert_eq!(clipped[(6, 7)].id(), 1);
+    assert_eq!(clipped.iter().filter(|tile| tile.id() != 1).count(), 1);
+    clipped.blit(&stamp, 7, 6, false);
+    assert_eq!(clipped[(7, 6)].id(), 2);
+    assert!(clipped[(7, 7)].is_empty());
+
+    let mut untouched = layer.clone();
+    untouched.blit(&stamp, 8, 0, false);
+    untouched.blit(&stamp, u32::MAX, u32::MAX, false);
+    assert_eq!(untouched, layer);
+}
+
+#[test]
+fn id_grid() {
+    let grid = vec![vec![1, 2, 3], vec![4, 5, 6], vec![7, 8, 0xFFFF]];
+    let layer = Layer::from_id_grid(&grid).expect("grid is rectangular");
+    assert_eq!((layer.width(), layer.height()), (3, 3));
+    assert_eq!(layer[(2, 0)].id(), 3);
+    assert_eq!(layer[(0, 2)].id(), 7);
+    assert!(layer[(2, 2)].is_empty());
+    assert_eq!(layer.to_id_grid(), grid);
+
+    let err = Layer::from_id_grid(&[vec![1, 2], vec![3, 4], vec![5]]).unwrap_err();
+    assert_eq!((err.row, err.expected, err.actual), (2, 2, 1));
+    assert_eq!(
+        Layer::from_id_grid(&[]).map(|layer| layer.to_id_grid()),
+        Ok(vec![])
+    );
+}
+
+#[test]
+fn resize_anchored() {
+    let mut layer = Layer::new();
+    layer.resize(8, 8);
+    for (i, tile) in layer.iter_mut().enumerate() {
+        *tile = Tile::from_id(i as u16);
+    }
+    let sublayer = layer.add_sublayer(b"\0\0");
+    for (i, cell) in sublayer.iter_mut().enumerate() {
+        cell[1] = i as u8;
+    }
+    layer.sublayers[0].set_default(b"ab");
+    let original = layer.clone();
+
+    layer.resize_anchored(10, 10, Anchor::Center);
+    assert_eq!((layer.width(), layer.height()), (10, 10));
+    for ((x, y), tile) in original.iter_coords() {
+        assert_eq!(&layer[(x + 1, y + 1)], tile);
+        assert_eq!(
+            &layer.sublayers[0][(x as u32 + 1, y as u32 + 1)],
+            &original.sublayers[0][(x as u32, y as u32)]
+        );
+    }
+    assert!(layer[(0, 0)].is_empty() && layer[(9, 9)].is_empty());
+    assert_eq!(&layer.sublayers[0][(9, 0)], b"ab");
+    assert_eq!(layer.sublayers[0].width(), 10);
+    layer.resize_anchored(8, 8, Anchor::Center);
+    assert_eq!(layer, original);
+
+    layer.resize_anchored(6, 9, Anchor::BottomRight);
+    assert_eq!(layer[(0, 1)], original[(2, 0)]);
+    assert!(layer[(0, 0)].is_empty());
+    layer.resize_anchored(8, 8, Anchor::BottomRight);
+    assert_eq!(layer[(7, 7)], original[(7, 7)]);
+    assert!(layer[(1, 0)].is_empty());
+
+    let mut top_left = original.clone();
+    top_left.resize_anchored(3, 3, Anchor::TopLeft);
+    let mut plain = original.clone();
+    plain.resize(3, 3);
+    assert_eq!(top_left, plain);
+}
+
+#[test]
+fn borrowed_into_iter() -> Result<(), ReadError> {
+    let mut map = TileMap::read(FILE)?;
+    let layer = &mut map.layers[0];
+    let area = (layer.width() * layer.height()) as usize;
+    assert_eq!((&*layer).into_iter().count(), area);
+    for tile in &mut *layer {
+        *tile = Tile::from_id(3);
+    }
+    let mut count = 0;
+    for tile in &*layer {
+        assert_eq!(tile.id(), 3);
+        count += 1;
+    }
+    assert_eq!(count, area);
+    Ok(())
+}
+
+#[test]
+fn repair() -> Result<(), Box<dyn std::error::Error>> {
+    let mut layer = Layer::new();
+    layer.resize(4, 4);
+    assert!(layer.is_consistent());
+    let mut sublayer = SubLayer::with_size(b"ab", 2, 2);
+    sublayer[(1, 0)].copy_from_slice(b"cd");
+    layer.sublayers.push(sublayer);
+    assert!(!layer.is_consistent());
+
+    let mut map = TileMap::new();
+    map.layers.push(Layer::new());
+    map.layers.push(layer.clone());
+    let strict = WriteOptions {
+        strict: true,
+        ..WriteOptions::default()
+    };
+    assert!(matches!(
+        map.write_with_options(Vec::new(), &strict),
+        Err(WriteError::InconsistentLayer(1))
+    ));
+
+    layer.repair();
+    assert!(layer.is_consistent());
+    let sublayer = &layer.sublayers[0];
+    assert_eq!((sublayer.width(), sublayer.height()), (4, 4));
+    assert_eq!(sublayer.data().len(), 32);
+    // Cells keep their place in the data, rather than their position
+    assert_eq!(&sublayer.data()[..8], b"abcdabab");
+    assert!(sublayer.iter().skip(4).all(|cell| cell == b"ab"));
+
+    map.layers[1] = layer;
+    map.write_with_options(Vec::new(), &strict)?;
+    Ok(())
+}
+
+#[test]
+fn builder() {
+    let layer = Layer::builder()
+        .size(8, 8)
+        .tileset(2)
+        .opacity(0.5)
+        .offset((-4, 12))
+        .wrap((true, false))
+        .tile_dimensions((32, 24))
+        .build();
+    assert_eq!((layer.width(), layer.height()), (8, 8));
+    assert_eq!(layer.iter().count(), 64);
+    assert!(layer.iter().all(|tile| *tile == Tile::default()));
+    assert_eq!(layer.tileset, 2);
+    assert_eq!(layer.opacity, 0.5);
+    assert_eq!(layer.offset, (-4, 12));
+    assert_eq!(layer.wrap, (true, false));
+    assert_eq!(layer.tile_dimensions, (32, 24));
+    assert!(layer.visible);
+    assert_eq!(LayerBuilder::new().build(), Layer::new());
+}
+
+#[test]
+fn tile_census() -> Result<(), ReadError> {
+    let map = TileMap::read(FILE)?;
+    let census = map.tile_census();
+    assert!(!census.contains_key(&Tile::default().id()));
+    let tiles = map
+        .layers
+        .iter()
+        .map(|layer| layer.width() as usize * layer.height() as usize)
+        .sum::<usize>();
+    let empties = map
+        .layers
+        .iter()
+        .flatten()
+        .filter(|tile| **tile == Tile::default())
+        .count();
+    assert!(empties > 0);
+    assert_eq!(census.values().sum::<usize>(), tiles - empties);
+    assert_eq!(
+        map.layers.iter().map(Layer::non_empty_count).sum::<usize>(),
+        tiles - empties
+    );
+    Ok(())
+}
+
+#[test]
+fn index_overflow() {
+    let mut layer = Layer::new();
+    layer.resize(2, 2);
+    layer[(1, 0)] = Tile::from_id(0x1234);
+    // (1, usize::MAX / 2 + 1) wraps around to the index of (1, 0) without overflow checks
+    let wrapping = (1, usize::MAX / 2 + 1);
+    assert_eq!(layer.get(wrapping), None);
+    assert_eq!(layer.get_mut(wrapping), None);
+    assert_eq!(layer.get((0, usize::MAX)), None);
+    assert!(std::panic::catch_unwind(|| layer[wrapping]).is_err());
+
+    // The area of this sublayer only just fits in a u32, but it has no data to allocate
+    let mut sublayer = SubLayer::with_size(&[], 0x1_0000, 0xFFFF);
+    let last = (0xFFFF, 0xFFFE);
+    assert_eq!(sublayer.get(last), Some([].as_slice()));
+    assert_eq!(&sublayer[last], &[]);
+    assert_eq!(sublayer.get_mut((0x1_0000, 0)), None);
+}
+
+#[test]
+fn default_tile() {
+    let empty = Tile::from_id(0x0000);
+    let mut layer = Layer::new();
+    layer.resize_with(2, 2, empty);
+    assert!(layer.iter().all(|tile| *tile == empty));
+    // Plain resizing still uses the default tile
+    layer.resize(3, 2);
+    assert_eq!(layer[(2, 0)], Tile::default());
+    assert_eq!(layer[(1, 1)], empty);
+
+    layer.default_tile = empty;
+    layer.resize_anchored(3, 3, Anchor::BottomRight);
+    assert!(layer.iter().take(3).all(|tile| *tile == empty));
+    layer.clear();
+    assert!(layer.iter().all(|tile| *tile == empty));
+
+    let built = Layer::builder().size(2, 1).default_tile(empty).build();
+    assert!(built.iter().all(|tile| *tile == empty));
+    assert_ne!(built, Layer::builder().size(2, 1).build());
+}
+
+#[test]
+fn remove_empty_layers() -> Result<(), Box<dyn std::error::Error>> {
+    let mut map = TileMap::new();
+    map.layers.push(Layer::new());
+    map.layers
+        .push(Layer::builder().size(2, 2).tileset(1).build());
+    map.layers.push(Layer::builder().size(0, 3).build());
+    map.layers
+        .push(Layer::builder().size(1, 1).tileset(2).build());
+    assert!(map.layers[0].is_empty() && map.layers[2].is_empty());
+    assert!(!map.layers[1].is_empty());
+
+    assert_eq!(map.remove_empty_layers(), 2);
+    let tilesets = map
+        .layers
+        .iter()
+        .map(|layer| layer.tileset)
+        .collect::<Vec<_>>();
+    assert_eq!(tilesets, [1, 2]);
+    assert_eq!(map.remove_empty_layers(), 0);
+    assert_eq!(TileMap::from_bytes(&map.to_bytes()?)?, map);
+    Ok(())
+}
+
+#[test]
+fn retain() -> Result<(), ReadError> {
+    let mut map = TileMap::read(FILE)?;
+    let visible = map.layers[0].clone();
+    let mut hidden = Layer::builder().size(2, 2).visible(false).build();
+    map.layers.push(hidden.clone());
+    map.layers.push(visible.clone());
+    hidden.tileset = 1;
+    map.layers.insert(0, hidden);
+
+    map.retain_layers(|layer| layer.visible);
+    assert_eq!(map.layers, [visible.clone(), visible]);
+
+    map.retain_properties(|key, value| key != "String" && !value.is_float());
+    let mut keys = map.properties.keys().collect::<Vec<_>>();
+    keys.sort_unstable();
+    assert_eq!(keys, ["Integer"]);
+    Ok(())
+}
+
+#[test]
+fn tile_slices() -> Result<(), ReadError> {
+    let mut map = TileMap::read(FILE)?;
+    let layer = &mut map.layers[0];
+    assert_eq!(layer.tiles().len(), 25);
+    assert_eq!(layer.tiles()[0].id(), layer[(0, 0)].id());
+    assert_eq!(layer.tiles()[7], layer[(2, 1)]);
+    assert_eq!(layer.row(1), Some(&layer.tiles()[5..10]));
+    assert_eq!(layer.row(4).map(<[Tile]>::len), Some(5));
+    assert_eq!(layer.row(5), None);
+
+    layer.tiles_mut()[24] = Tile::from_id(0x0102);
+    assert_eq!(layer[(4, 4)].id(), 0x0102);
+    Ok(())
+}
+
+#[test]
+fn write_layers() -> Result<(), Box<dyn std::error::Error>> {
+    let mut map = TileMap::read(FILE)?;
+    map.layers
+        .push(Layer::builder().size(3, 2).tileset(1).build());
+
+    let mut bytes = Vec::new();
+    map.write_layers(&mut bytes, &[0])?;
+    let read = TileMap::read(bytes.as_slice())?;
+    assert_eq!(read.layers, map.layers[..1]);
+    assert_eq!(read.properties, map.properties);
+    assert_eq!(read.tilesets, map.tilesets);
+
+    bytes.clear();
+    map.write_layers(&mut bytes, &[1, 0])?;
+    let read = TileMap::read(bytes.as_slice())?;
+    assert_eq!(read.layers, [map.layers[1].clone(), map.layers[0].clone()]);
+
+    bytes.clear();
+    assert!(matches!(
+        map.write_layers(&mut bytes, &[0, 2]),
+        Err(WriteError::NoSuchLayer(2))
+    ));
+    assert!(bytes.is_empty());
+    Ok(())
+}
+
+#[test]
+fn sanitize() -> Result<(), Box<dyn std::error::Error>> {
+    let mut map = TileMap::new();
+    let mut layer = Layer::builder().size(2, 2).opacity(f32::NAN).build();
+    layer.scroll = (f32::INFINITY, 0.5);
+    map.layers.push(layer);
+    let bytes = map.to_bytes()?;
+
+    // Raw values are kept by default
+    let raw = TileMap::from_bytes(&bytes)?;
+    assert!(raw.layers[0].opacity.is_nan());
+    assert_eq!(raw.layers[0].scroll.0, f32::INFINITY);
+
+    let options = ReadOptions {
+        clamp_floats: true,
+        ..ReadOptions::default()
+    };
+    let clamped = TileMap::read_with_options(bytes.as_slice(), &options)?;
+    assert_eq!(clamped.layers[0].opacity, 1.0);
+    assert_eq!(clamped.layers[0].scroll, (0.0, 0.5));
+
+    let mut layer = Layer::builder().opacity(2.5).build();
+    layer.scroll = (f32::NAN, f32::NEG_INFINITY);
+    layer.sanitize();
+    assert_eq!(layer.opacity, 1.0);
+    assert_eq!(layer.scroll, (0.0, 0.0));
+    layer.opacity = -0.5;
+    layer.sanitize();
+    assert_eq!(layer.opacity, 0.0);
+    Ok(())
+}
+
+#[test]
+fn same_tiles() -> Result<(), ReadError> {
+    let map = TileMap::read(FILE)?;
+    let layer = &map.layers[0];
+    let mut faded = layer.clone();
+    faded.opacity = 0.25;
+    assert!(layer.same_tiles(&faded));
+    assert_ne!(layer, &faded);
+
+    let mut moved = faded.clone();
+    moved.offset = (4, 4);
+    moved.visible = false;
+    moved.sublayers.clear();
+    assert!(layer.same_tiles(&moved));
+    moved[(0, 0)] = Tile::from_id(0x0102);
+    assert!(!layer.same_tiles(&moved));
+    // Same tiles, different size
+    let mut reshaped = layer.clone();
+    reshaped.resize(25, 1);
+    assert!(!layer.same_tiles(&reshaped));
+
+    let mut other = map.clone();
+    other.layers[0] = faded;
+    other.version = 4;
+    assert!(map.same_content(&other));
+    assert_ne!(map, other);
+    other.layers[0].sublayers.clear();
+    assert!(!map.same_content(&other));
+    Ok(())
+}
+
+/// A 3x2 layer with tile IDs counting up, and a sublayer whose cells count up too.
+fn counting_layer() -> Layer {
+    let mut layer = Layer::new();
+    layer.resize(3, 2);
+    for (i, tile) in layer.iter_mut().enumerate() {
+        *tile = Tile::from_id(i as u16);
+    }
+    let sublayer = layer.add_sublayer(b"\xFF\0");
+    for (i, cell) in sublayer.iter_mut().enumerate() {
+        cell.copy_from_slice(&[i as u8, 0x10]);
+    }
+    layer
+}
+
+#[test]
+fn shift_wrapping() {
+    let original = counting_layer();
+    let mut layer = original.clone();
+    layer.shift(1, 1, true);
+    let ids = layer.iter().map(Tile::id).collect::<Vec<_>>();
+    assert_eq!(ids, [5, 3, 4, 2, 0, 1]);
+    let cells = layer.sublayers[0].data().to_vec();
+    assert_eq!(
+        cells,
+        [5, 0x10, 3, 0x10, 4, 0x10, 2, 0x10, 0, 0x10, 1, 0x10]
+    );
+
+    // Shifting by whole sizes, or back again, gets the original
+    layer.shift(-1, -1, true);
+    assert_eq!(layer, original);
+    layer.shift(-6, 4, true);
+    assert_eq!(layer, original);
+}
+
+#[test]
+fn shift_filling() {
+    let mut layer = counting_layer();
+    layer.default_tile = Tile::from_id(0x0909);
+    layer.shift(-1, 0, false);
+    let ids = layer.iter().map(Tile::id).collect::<Vec<_>>();
+    assert_eq!(ids, [1, 2, 0x0909, 4, 5, 0x0909]);
+    assert_eq!(&layer.sublayers[0][(2, 0)], b"\xFF\0");
+    assert_eq!(&layer.sublayers[0][(0, 1)], &[4, 0x10]);
+
+    layer.shift(0, 2, false);
+    assert!(layer.iter().all(|tile| tile.id() == 0x0909));
+    assert!(layer.sublayers[0].iter().all(|cell| cell == b"\xFF\0"));
+}
+
+#[test]
+fn flatten_visible() {
+    let mut map = TileMap::new();
+    let mut bottom = Layer::new();
+    bottom.resize_with(3, 2, Tile::from_id(1));
+    let mut top = Layer::new();
+    top.resize(2, 2);
+    top[(1, 0)] = Tile::from_id(2);
+    top[(1, 1)] = Tile::from_id(3);
+    // One tile right and one tile up, in pixels
+    top.offset = (16, -16);
+    let mut hidden = Layer::new();
+    hidden.resize_with(3, 2, Tile::from_id(4));
+    hidden.visible = false;
+    let mut transparent = hidden.clone();
+    transparent.visible = true;
+    transparent.opacity = 0.0;
+    let mut mismatched = transparent.clone();
+    mismatched.opacity = 1.0;
+    mismatched.tile_dimensions = (8, 8);
+    map.layers = vec![bottom, top, hidden, transparent, mismatched];
+
+    let flattened = map.flatten_visible();
+    assert_eq!((flattened.width(), flattened.height()), (3, 3));
+    assert_eq!(flattened.offset, (0, -16));
+    assert_eq!(flattened.tile_dimensions, (16, 16));
+    let ids = flattened.iter().map(Tile::id).collect::<Vec<_>>();
+    assert_eq!(ids, [0xFFFF, 0xFFFF, 2, 1, 1, 3, 1, 1, 1]);
+
+    assert_eq!(TileMap::new().flatten_visible(), Layer::new());
+}
+
+#[test]
+fn pixel_size() {
+    let mut layer = Layer::new();
+    layer.resize(8, 8);
+    layer.tile_dimensions = (16, 16);
+    assert_eq!(layer.pixel_size(), (128, 128));
+    layer.offset = (-32, 48);
+    assert_eq!(layer.pixel_rect(), (-32, 48, 128, 128));
+
+    // Sizes past a u32 still fit
+    let wide = LayerBuilder::new()
+        .size(0x20000, 1)
+        .tile_dimensions((u16::MAX, u16::MAX))
+        .build();
+    assert_eq!(wide.pixel_size(), (0x20000 * 0xFFFF, 0xFFFF));
+}
+
+#[test]
+fn paste() {
+    let mut layer = Layer::new();
+    layer.resize(4, 3);
+    let block = [1, 2, 3, 4].map(Tile::from_id);
+    layer.paste(1, 1, 2, 2, &block).unwrap();
+    let ids = layer.iter().map(Tile::id).collect::<Vec<_>>();
+    #[rustfmt::skip]
+    assert_eq!(ids, [
+        0xFFFF, 0xFFFF, 0xFFFF, 0xFFFF,
+        0xFFFF, 1,      2,      0xFFFF,
+        0xFFFF, 3,      4,      0xFFFF,
+    ]);
+    // Pasting back what was cropped changes nothing
+    let cropped = layer.crop(1, 0, 3, 2).unwrap();
+    let before = layer.clone();
+    layer.paste(1, 0, 3, 2, cropped.tiles()).unwrap();
+    assert_eq!(layer, before);
+
+    assert_eq!(
+        layer.paste(3, 2, 2, 2, &block),
+        Err(PasteError::OutOfBounds {
+            region: (3, 2, 2, 2),
+            width: 4,
+            height: 3
+        })
+    );
+    assert_eq!(
+        layer.paste(0, 0, 2, 1, &block),
+        Err(PasteError::WrongLength {
+            expected: 2,
+            actual: 4
+        })
+    );
+    assert_eq!(layer, before);
+    assert!(layer.paste(4, 3, 0, 0, &[]).is_ok());
+}
+
+#[test]
+fn from_tiles() {
+    let layer = Layer::from_tiles(3, 3, (0..9).map(Tile::from_id)).unwrap();
+    assert_eq!((layer.width(), layer.height()), (3, 3));
+    assert!(layer.is_consistent());
+    assert_eq!(layer[(2, 0)], Tile::from_id(2));
+    assert_eq!(layer[(0, 2)], Tile::from_id(6));
+
+    assert_eq!(
+        Layer::from_tiles(3, 3, (0..8).map(Tile::from_id)),
+        Err(LayerShapeError::WrongLength {
+            expected: 9,
+            actual: 8
+        })
+    );
+    // Endless iterators are only read one tile past the end
+    let err = Layer::from_tiles(3, 3, std::iter::repeat(Tile::EMPTY)).unwrap_err();
+    assert_eq!(
+        err,
+        LayerShapeError::WrongLength {
+            expected: 9,
+            actual: 10
+        }
+    );
+    assert_eq!(err.to_string(), "more than 9 tiles were given");
+    assert_eq!(Layer::from_tiles(0, 0, []), Ok(Layer::new()));
+    // Sizes too big for a layer are rejected before taking any tiles
+    let err = Layer::from_tiles(0x10000, 0x10000, std::iter::repeat(Tile::EMPTY)).unwrap_err();
+    assert_eq!(
+        err,
+        LayerShapeError::AreaOverflow(AreaOverflow {
+            width: 0x10000,
+            height: 0x10000
+        })
+    );
+    assert_eq!(err.to_string(), "area of 65536x65536 overflows a u32");
+}
+
+#[test]
+fn trim() {
+    let mut layer = Layer::new();
+    layer.resize(8, 8);
+    layer.add_sublayer(&[0]);
+    for y in 3..6 {
+        for x in 2..5 {
+            layer[(x, y)] = Tile::from_id((y * 8 + x) as u16);
+            layer.sublayers[0].set_u8((x as u32, y as u32), 1);
+        }
+    }
+    // Empty tiles inside the content are kept
+    layer[(3, 4)] = Tile::EMPTY;
+    let expected = layer.crop(2, 3, 3, 3).unwrap();
+    assert_eq!(layer.trim(), (2, 3));
+    assert_eq!(layer, expected);
+    assert_eq!((layer.width(), layer.height()), (3, 3));
+    assert_eq!(layer[(0, 0)], Tile::from_id(3 * 8 + 2));
+    assert_eq!(layer.sublayers[0].as_u8_grid(), Some([1; 9].as_slice()));
+
+    // Trimming again changes nothing
+    assert_eq!(layer.trim(), (0, 0));
+    assert_eq!(layer, expected);
+
+    let mut empty = Layer::new();
+    empty.resize(4, 4);
+    assert_eq!(empty.trim(), (0, 0));
+    assert_eq!((empty.width(), empty.height()), (0, 0));
+}
+
+#[test]
+fn empty_tile() -> Result<(), Box<dyn std::error::Error>> {
+    let mut map = TileMap::read(FILE)?;
+    assert_eq!(map.empty_tile(), Tile::default());
+    let zero = Tile::from_id(0);
+    map.set_empty_tile(zero);
+    assert_eq!(map.empty_tile(), zero);
+
+    let layer = &mut map.layers[0];
+    assert_eq!(layer.default_tile, zero);
+    layer.clear();
+    layer[(1, 2)] = Tile::default();
+    layer[(3, 3)] = Tile::default();
+    // 0xFFFF counts as occupied now
+    assert_eq!(layer.non_empty_count(), 2);
+
+    layer.resize(6, 6);
+    assert_eq!(layer[(5, 5)], zero);
+    assert_eq!(layer.non_empty_count(), 2);
+    assert_eq!(layer.trim(), (1, 2));
+    assert_eq!((layer.width(), layer.height()), (3, 2));
+    assert_eq!(layer[(0, 0)], Tile::default());
+    assert_eq!(layer[(1, 0)], zero);
+    Ok(())
+}
+
+#[test]
+fn empty_tile_added_layers() {
+    let zero = Tile::from_id(0);
+    let mut map = TileMap::new();
+    map.set_empty_tile(zero);
+    // This layer is added afterwards, so it keeps the tile default as its own default tile
+    let mut layer = Layer::new();
+    layer.resize_with(3, 2, zero);
+    layer[(0, 1)] = Tile::default();
+    layer[(2, 1)] = Tile::from_id(7);
+    map.layers.push(layer);
+    assert_eq!(map.layers[0].default_tile, Tile::default());
+
+    // 0xFFFF counts as occupied across the map regardless
+    assert_eq!(map.non_empty_count(), 2);
+    assert_eq!(map.stats().non_empty_tiles, 2);
+    let census = map.tile_census();
+    assert_eq!(census.len(), 2);
+    assert_eq!(census[&0xFFFF], 1);
+    assert_eq!(census[&7], 1);
+
+    let flattened = map.flatten_visible();
+    assert_eq!(flattened.default_tile, zero);
+    assert_eq!(flattened.non_empty_count(), 2);
+}
+
+#[test]
+fn swap_tile_endianness() -> Result<(), Box<dyn std::error::Error>> {
+    let map = TileMap::read(FILE)?;
+    let original = map.layers[0].clone();
+    let mut layer = original.clone();
+    layer.swap_tile_endianness();
+    for (swapped, tile) in layer.iter().zip(original.iter()) {
+        assert_eq!(swapped.id(), tile.id().swap_bytes());
+        let [x, y] = tile.view().position;
+        assert_eq!(swapped.view().position, [y, x]);
+    }
+    layer.swap_tile_endianness();
+    assert_eq!(layer, original);
+
+    // A tile built from a little endian ID has its position swapped, which this fixes
+    let mut layer = Layer::new();
+    layer.resize(1, 1);
+    layer[(0, 0)] = Tile::from_id(u16::from_le_bytes([5, 3]));
+    assert_eq!(layer[(0, 0)].view().position, [3, 5]);
+    layer.swap_tile_endianness();
+    assert_eq!(layer[(0, 0)], Tile::from_tile_coords(5, 3));
+    Ok(())
+}
+
+#[test]
+fn empty_tile_compositing() {
+    let zero = Tile::from_id(0);
+    let mut map = TileMap::new();
+    let mut bottom = Layer::new();
+    bottom.resize_with(2, 1, Tile::from_id(7));
+    let mut top = Layer::new();
+    top.resize_with(2, 1, zero);
+    top[(1, 0)] = Tile::default();
+    map.layers = vec![bottom, top];
+    map.set_empty_tile(zero);
+
+    // 0x0000 lets the tile under it show through, and 0xFFFF doesn't
+    let ids = map
+        .flatten_visible()
+        .iter()
+        .map(Tile::id)
+        .collect::<Vec<_>>();
+    assert_eq!(ids, [7, 0xFFFF]);
+    let census = map.tile_census();
+    assert_eq!(census.len(), 2);
+    assert_eq!(census[&7], 2);
+    assert_eq!(census[&0xFFFF], 1);
+}
@@ -0,0 +1,42 @@
+//! Runs with and without the `std` feature, to check that the slice API works either way:
+//! `cargo test --no-default-features --features libflate-backend --test no_std`
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec;
+use ct_tilemap::{Property, ReadError, Tile, TileMap};
+
+mod common;
+use common::FILE;
+
+#[test]
+fn parse_slice() -> Result<(), ReadError> {
+    let map = TileMap::from_bytes(FILE)?;
+    assert_eq!(map.version, 5);
+    assert_eq!(map.properties["Integer"], Property::Integer(196));
+    assert_eq!(map.properties["Float"], Property::Float(2.2));
+    assert_eq!(map.get_string("String"), Some("Hello, world!"));
+    assert_eq!(map.tilesets.len(), 2);
+    assert_eq!(map.layers.len(), 1);
+    let layer = &map.layers[0];
+    assert_eq!((layer.width(), layer.height()), (5, 5));
+    assert_eq!(layer.sublayers.len(), 1);
+    assert!(layer.is_consistent());
+
+    let mut edited = map.clone();
+    edited.layers[0][(2, 3)] = Tile::from_tile_coords(4, 1);
+    let mut buf = vec![0; 1024];
+    let len = edited
+        .write_to_slice(&mut buf)
+        .expect("failed to write map");
+    assert_eq!(TileMap::from_bytes(&buf[..len])?, edited);
+    assert_eq!(edited.to_bytes().expect("failed to write map"), &buf[..len]);
+
+    // Slices that end early are still reported as such
+    assert!(matches!(
+        TileMap::from_bytes(&FILE[..9]),
+        Err(ReadError::UnexpectedEof { offset: 0, .. })
+    ));
+    Ok(())
+}
@@ -0,0 +1,119 @@
+use const_str::concat_bytes;
+use ct_tilemap::{Tile, TileMap};
+
+/// A 2x2 layer without tile dimensions of its own, as stored by versions before 2.
+const LAYER: &[u8] = concat_bytes!(
+    2_u32.to_le_bytes(),
+    2_u32.to_le_bytes(), // Dimensions
+    0,
+    0xFF, // Tileset and collision
+    3_i32.to_le_bytes(),
+    (-4_i32).to_le_bytes(), // Offset
+    1_f32.to_le_bytes(),
+    0.5_f32.to_le_bytes(), // Scroll
+    1,
+    0,                   // Wrap
+    1,                   // Visible
+    1_f32.to_le_bytes(), // Opacity
+    // Data blocks
+    1,       // One data block
+    b"MAIN", // Main tile data
+    // Compressed data
+    16_u32.to_le_bytes(), // Length
+    0x78,
+    0xda,
+    0x63,
+    0x64,
+    0x62,
+    0x66,
+    0xf9,
+    0xff,
+    0x9f,
+    0x81,
+    0x01,
+    0x00,
+    0x07,
+    0x3d,
+    0x02,
+    0x09,
+);
+
+/// The blocks before the layers, with the deprecated `MAP ` block holding 32x24 global tile dimensions.
+const HEADER: &[u8] = concat_bytes!(
+    b"MAP ",              // Global tile dimensions
+    4_u32.to_le_bytes(),  // Block length
+    32_u16.to_le_bytes(), // Tile width
+    24_u16.to_le_bytes(), // Tile height
+    b"TILE",              // Tilesets
+    15_u32.to_le_bytes(), // Block length
+    1,                    // Number of tilesets
+    0,
+    0xda,
+    0x89,
+    0x72, // xBGR color
+    8,
+    b"tiles.png",
+);
+
+/// A version 0 tilemap, which stores its layer count in a single byte.
+const VERSION_0: &[u8] = concat_bytes!(
+    b"ACHTUNG!", // Magic string
+    b"\x00\x01", // Version 0
+    HEADER,
+    b"LAYR",              // Layers
+    59_u32.to_le_bytes(), // Block length
+    1,                    // Number of layers
+    LAYER,
+);
+
+/// A version 1 tilemap, which stores its layer count in two bytes.
+const VERSION_1: &[u8] = concat_bytes!(
+    b"ACHTUNG!", // Magic string
+    b"\x01\x01", // Version 1
+    HEADER,
+    b"LAYR",              // Layers
+    60_u32.to_le_bytes(), // Block length
+    1_u16.to_le_bytes(),  // Number of layers
+    LAYER,
+);
+
+#[test]
+fn global_tile_dimensions() -> Result<(), Box<dyn std::error::Error>> {
+    for (version, file) in [(0, VERSION_0), (1, VERSION_1)] {
+        let map = TileMap::read(file)?;
+        assert_eq!(map.version, version);
+        assert!(map.properties.is_empty());
+        assert!(map.unknown_blocks.is_empty());
+        assert_eq!(map.tilesets.len(), 1);
+        assert_eq!(map.tilesets[0].path, "tiles.png");
+        assert_eq!(map.layers.len(), 1);
+
+        let layer = &map.layers[0];
+        assert_eq!(layer.tile_dimensions, (32, 24));
+        assert_eq!((layer.width(), layer.height()), (2, 2));
+        assert_eq!((layer.tileset, layer.collision), (0, 0xFF));
+        assert_eq!(layer.offset, (3, -4));
+        assert_eq!(layer.scroll, (1.0, 0.5));
+        assert_eq!(layer.wrap, (true, false));
+        assert!(layer.is_consistent());
+        let ids = layer.iter().map(Tile::id).collect::<Vec<_>>();
+        assert_eq!(ids, [0x0102, 0x0304, 0xFFFF, 0x0000]);
+
+        // Writing it back out at the same version keeps the global tile dimensions
+        let mut written = Vec::new();
+        map.write_versioned(&mut written, version)?;
+        assert_eq!(TileMap::from_bytes(&written)?, map);
+        assert_eq!(&written[10..22], &file[10..22]);
+    }
+    Ok(())
+}
+
+#[test]
+fn missing_global_tile_dimensions() -> Result<(), Box<dyn std::error::Error>> {
+    // Without a `MAP ` block, layers fall back to 16x16 tiles
+    let file = [&VERSION_0[..10], &VERSION_0[22..]].concat();
+    let map = TileMap::from_bytes(&file)?;
+    assert_eq!(map.layers[0].tile_dimensions, (16, 16));
+    assert_eq!(map.layers[0].offset, (3, -4));
+    Ok(())
+}
@@ -0,0 +1,36 @@
+#![cfg(feature = "rayon")]
+
+use ct_tilemap::{Layer, Tile, TileMap, WriteOptions};
+
+#[test]
+fn parallel_matches_sequential() -> Result<(), Box<dyn std::error::Error>> {
+    let mut map = TileMap::new();
+    for i in 0..64u16 {
+        let mut layer = Layer::new();
+        layer.resize(32 + i as u32, 16);
+        for (j, tile) in layer.iter_mut().enumerate() {
+            *tile = Tile::from_id((j as u16).wrapping_mul(i) % 97);
+        }
+        let sublayer = layer.add_sublayer(&[i as u8, 0]);
+        for (j, cell) in sublayer.iter_mut().enumerate() {
+            cell[1] = (j % 7) as u8;
+        }
+        map.layers.push(layer);
+    }
+    // Empty layers don't have any data to compress
+    map.layers.insert(10, Layer::new());
+
+    let mut parallel = Vec::new();
+    map.write_with_options(&mut parallel, &WriteOptions::default())?;
+    let mut sequential = Vec::new();
+    map.write_with_options(
+        &mut sequential,
+        &WriteOptions {
+            parallel: false,
+            ..WriteOptions::default()
+        },
+    )?;
+    assert!(parallel == sequential, "parallel output differed");
+    assert_eq!(TileMap::read(parallel.as_slice())?, map);
+    Ok(())
+}
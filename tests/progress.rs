@@ -0,0 +1,26 @@
+use ct_tilemap::{ReadOptions, TileMap};
+
+mod common;
+use common::FILE;
+
+#[test]
+fn callbacks() -> Result<(), Box<dyn std::error::Error>> {
+    let mut blocks = Vec::new();
+    let mut layers = Vec::new();
+    let map = TileMap::read_with_progress(
+        FILE,
+        &ReadOptions::default(),
+        |id, len, position| blocks.push((id, len, position)),
+        |index, total| layers.push((index, total)),
+    )?;
+    let ids = blocks.iter().map(|(id, _, _)| id).collect::<Vec<_>>();
+    assert_eq!(ids, [b"MAP ", b"TILE", b"LAYR"]);
+    assert_eq!(blocks[0], (*b"MAP ", 51, 10));
+    // Each block starts after the header and body of the one before it
+    for pair in blocks.windows(2) {
+        assert_eq!(pair[1].2, pair[0].2 + 8 + pair[0].1 as u64);
+    }
+    assert_eq!(layers, [(0, 1)]);
+    assert_eq!(map, TileMap::read(FILE)?);
+    Ok(())
+}
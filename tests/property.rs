@@ -0,0 +1,180 @@
+use const_str::concat_bytes;
+use ct_tilemap::{DuplicatePolicy, Property, ReadError, ReadOptions, TileMap};
+
+mod common;
+use common::FILE;
+
+#[test]
+fn typed_accessors() -> Result<(), Box<dyn std::error::Error>> {
+    let map = TileMap::read(FILE)?;
+    let integer = &map.properties["Integer"];
+    assert!(integer.is_integer());
+    assert_eq!(integer.as_i32(), Some(196));
+    assert_eq!(integer.as_f32(), None);
+    assert_eq!(integer.as_bytes(), None);
+
+    let float = &map.properties["Float"];
+    assert!(float.is_float());
+    assert_eq!(float.as_f32(), Some(2.2));
+    assert_eq!(float.as_i32(), None);
+    assert_eq!(float.as_str(), None);
+
+    let string = &map.properties["String"];
+    assert!(string.is_string());
+    assert_eq!(string.as_bytes(), Some(b"Hello, world!".as_slice()));
+    assert_eq!(string.as_str(), Some("Hello, world!"));
+    assert!(!string.is_integer() && !string.is_float());
+
+    let invalid = Property::String(vec![0xFF, 0xFE]);
+    assert_eq!(invalid.as_bytes(), Some([0xFF, 0xFE].as_slice()));
+    assert_eq!(invalid.as_str(), None);
+    Ok(())
+}
+
+#[test]
+fn typed_map_properties() -> Result<(), Box<dyn std::error::Error>> {
+    let mut map = TileMap::read(FILE)?;
+    assert_eq!(map.get_int("Integer"), Some(196));
+    assert_eq!(map.get_float("Float"), Some(2.2));
+    assert_eq!(map.get_string("String"), Some("Hello, world!"));
+    assert_eq!(map.get_int("Float"), None);
+    assert_eq!(map.get_string("Missing"), None);
+
+    assert_eq!(map.set_int("Integer", -5), Some(Property::Integer(196)));
+    assert_eq!(map.set_float(String::from("Speed"), 0.5), None);
+    map.set_string("Name", "Level 1");
+    map.set_string("Bytes", vec![0xFF]);
+    assert_eq!(map.get_int("Integer"), Some(-5));
+    assert_eq!(map.get_float("Speed"), Some(0.5));
+    assert_eq!(map.get_string("Name"), Some("Level 1"));
+    assert_eq!(map.get_string("Bytes"), None);
+    assert_eq!(map.properties["Bytes"].as_bytes(), Some([0xFF].as_slice()));
+
+    assert_eq!(map.remove_property("Speed"), Some(Property::Float(0.5)));
+    assert_eq!(map.remove_property("Speed"), None);
+    assert_eq!(map.get_float("Speed"), None);
+    Ok(())
+}
+
+#[test]
+fn content_hash() -> Result<(), Box<dyn std::error::Error>> {
+    let map = TileMap::read(FILE)?;
+    let mut forwards = map.clone();
+    let mut backwards = map.clone();
+    forwards.properties.clear();
+    backwards.properties.clear();
+    let mut properties = map.properties.clone().into_iter().collect::<Vec<_>>();
+    properties.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+    for (key, value) in properties.iter().cloned() {
+        forwards.properties.insert(key, value);
+    }
+    for (key, value) in properties.into_iter().rev() {
+        backwards.properties.insert(key, value);
+    }
+    assert_eq!(forwards.content_hash(), backwards.content_hash());
+    assert_eq!(forwards.content_hash(), map.content_hash());
+
+    backwards.set_int("Integer", 197);
+    assert_ne!(forwards.content_hash(), backwards.content_hash());
+    Ok(())
+}
+
+#[test]
+fn booleans() -> Result<(), Box<dyn std::error::Error>> {
+    let mut map = TileMap::new();
+    map.properties.insert("Solid".into(), true.into());
+    map.properties.insert("Hidden".into(), false.into());
+    map.properties.insert("Count".into(), Property::Integer(2));
+    assert_eq!(map.properties["Solid"].as_bool(), Some(true));
+    assert!(map.properties["Hidden"].is_bool());
+    assert_eq!(format!("{:?}", map.properties["Solid"]), "true");
+    let bytes = map.to_bytes()?;
+
+    // Booleans are written as integers
+    let read = TileMap::from_bytes(&bytes)?;
+    assert_eq!(read.properties["Solid"], Property::Integer(1));
+    assert_eq!(read.properties["Hidden"], Property::Integer(0));
+    assert_eq!(read.properties["Count"], Property::Integer(2));
+
+    let options = ReadOptions {
+        coerce_bools: true,
+        ..ReadOptions::default()
+    };
+    let coerced = TileMap::read_with_options(bytes.as_slice(), &options)?;
+    assert_eq!(coerced.properties, map.properties);
+    Ok(())
+}
+
+const DUPLICATE_KEY: &[u8] = concat_bytes!(
+    b"ACHTUNG!",          // Magic string
+    b"\x05\x01",          // Version 5
+    b"MAP ",              // Property mapping
+    24_u32.to_le_bytes(), // Block length
+    2_u16.to_le_bytes(),  // Number of properties
+    4,
+    b"Hello", // Property 1
+    0,
+    1_i32.to_le_bytes(), // Integer
+    4,
+    b"Hello", // Property 2, with the same key
+    0,
+    2_i32.to_le_bytes(), // Integer
+);
+
+#[test]
+fn duplicate_keys() -> Result<(), Box<dyn std::error::Error>> {
+    let read = |on_duplicate_property| {
+        let options = ReadOptions {
+            on_duplicate_property,
+            ..ReadOptions::default()
+        };
+        TileMap::read_with_options(DUPLICATE_KEY, &options)
+    };
+    assert_eq!(TileMap::read(DUPLICATE_KEY)?.get_int("Hello"), Some(2));
+    assert_eq!(read(DuplicatePolicy::KeepLast)?.get_int("Hello"), Some(2));
+    assert_eq!(read(DuplicatePolicy::KeepFirst)?.get_int("Hello"), Some(1));
+    assert!(matches!(
+        read(DuplicatePolicy::Error),
+        Err(ReadError::DuplicateProperty(key)) if key == "Hello"
+    ));
+    Ok(())
+}
+
+#[test]
+fn properties_sorted() -> Result<(), Box<dyn std::error::Error>> {
+    let mut map = TileMap::read(FILE)?;
+    assert_eq!(
+        map.properties_sorted(),
+        [
+            ("Float", &Property::Float(2.2)),
+            ("Integer", &Property::Integer(196)),
+            ("String", &Property::String(b"Hello, world!".to_vec())),
+        ]
+    );
+
+    // The alternate formatter prints the properties in the same order every time,
+    // along with the lossy strings
+    for key in ["Float", "Integer", "String"] {
+        map.lossy_strings
+            .insert(key.to_owned(), key.as_bytes().to_vec());
+    }
+    let printed = format!("{map:#?}");
+    let float = printed.find("\"Float\"").expect("missing Float");
+    let integer = printed.find("\"Integer\"").expect("missing Integer");
+    let string = printed.find("\"String\"").expect("missing String");
+    assert!(float < integer && integer < string);
+    let mut reinserted = map.clone();
+    reinserted.properties.clear();
+    reinserted.lossy_strings.clear();
+    for (key, value) in map.properties_sorted().into_iter().rev() {
+        reinserted.properties.insert(key.to_owned(), value.clone());
+        reinserted
+            .lossy_strings
+            .insert(key.to_owned(), map.lossy_strings[key].clone());
+    }
+    assert_eq!(format!("{reinserted:#?}"), printed);
+
+    map.properties.clear();
+    assert!(map.properties_sorted().is_empty());
+    Ok(())
+}
@@ -0,0 +1,37 @@
+#![cfg(feature = "serde")]
+
+use ct_tilemap::{Layer, SubLayer, TileMap};
+
+mod common;
+use common::FILE;
+
+#[test]
+fn serde_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+    let map = TileMap::read(FILE)?;
+    let json = serde_json::to_string(&map)?;
+    let same_map: TileMap = serde_json::from_str(&json)?;
+    assert_eq!(map, same_map, "Serde round-trip failed!");
+    Ok(())
+}
+
+#[test]
+fn invalid_sublayer() {
+    serde_json::from_str::<SubLayer>(
+        r#"{"default_value": [0, 0], "width": 2, "height": 2, "data": [0, 0, 0]}"#,
+    )
+    .expect_err("should have failed to deserialize a sublayer with too little data");
+    serde_json::from_str::<SubLayer>(
+        r#"{"default_value": [0, 0, 0, 0, 0], "width": 0, "height": 0, "data": []}"#,
+    )
+    .expect_err("should have failed to deserialize an oversized default value");
+}
+
+#[test]
+fn invalid_layer() -> Result<(), Box<dyn std::error::Error>> {
+    let map = TileMap::read(FILE)?;
+    let mut json = serde_json::to_value(&map.layers[0])?;
+    json["width"] = 4.into();
+    serde_json::from_value::<Layer>(json)
+        .expect_err("should have failed to deserialize a layer with mismatched data");
+    Ok(())
+}
@@ -0,0 +1,360 @@
+use ct_tilemap::{
+    AreaOverflow, DefaultTooLong, Layer, OutOfBounds, ReadError, ReadOptions, SubLayer,
+    SubLayerLink, SubLayerSetError, SubLayerShapeError, Tile, TileMap,
+};
+
+mod common;
+use common::FILE;
+
+#[test]
+fn typed_cells() {
+    let mut layer = Layer::new();
+    layer.resize(4, 4);
+    let sublayer = layer.add_sublayer(&[0; 4]);
+    assert!(sublayer.set_u32((1, 2), 0xDEAD_BEEF));
+    assert_eq!(sublayer.get_u32((1, 2)), Some(0xDEAD_BEEF));
+    assert_eq!(&sublayer[(1, 2)], &[0xEF, 0xBE, 0xAD, 0xDE]);
+    assert_eq!(sublayer.get_u32((0, 0)), Some(0));
+    // Wrong widths
+    assert_eq!(sublayer.get_u16((1, 2)), None);
+    assert_eq!(sublayer.get_u8((1, 2)), None);
+    assert!(!sublayer.set_u16((1, 2), 0));
+    assert!(!sublayer.set_u8((1, 2), 0));
+    // Out of bounds
+    assert_eq!(sublayer.get_u32((4, 0)), None);
+    assert!(!sublayer.set_u32((0, 4), 0));
+
+    let sublayer = layer.add_sublayer(&[0; 2]);
+    assert!(sublayer.set_u16((3, 3), 0xBEEF));
+    assert_eq!(sublayer.get_u16((3, 3)), Some(0xBEEF));
+    let sublayer = layer.add_sublayer(&[7]);
+    assert_eq!(sublayer.get_u8((3, 3)), Some(7));
+    assert!(sublayer.set_u8((3, 3), 0xAB));
+    assert_eq!(sublayer.get_u8((3, 3)), Some(0xAB));
+}
+
+#[test]
+fn typed_grids() {
+    let mut layer = Layer::new();
+    layer.resize(3, 2);
+    let bytes = layer.add_sublayer(&[0]);
+    bytes.set_u8((2, 1), 9);
+    let grid = bytes.as_u8_grid().unwrap();
+    assert_eq!(grid, [0, 0, 0, 0, 0, 9]);
+    // The grid is the backing data itself
+    assert_eq!(grid.as_ptr(), bytes.data().as_ptr());
+    assert_eq!(bytes.as_u16_grid(), None);
+    assert_eq!(bytes.as_u32_grid(), None);
+
+    let shorts = layer.add_sublayer(&[1, 0]);
+    shorts.set_u16((0, 1), 0xBEEF);
+    assert_eq!(shorts.as_u16_grid(), Some(vec![1, 1, 1, 0xBEEF, 1, 1]));
+    assert_eq!(shorts.as_u8_grid(), None);
+
+    let words = layer.add_sublayer(&[0; 4]);
+    words.set_u32((1, 0), 0xDEAD_BEEF);
+    assert_eq!(words.as_u32_grid(), Some(vec![0, 0xDEAD_BEEF, 0, 0, 0, 0]));
+    assert_eq!(words.as_u16_grid(), None);
+    assert_eq!(layer.add_sublayer(&[0; 3]).as_u32_grid(), None);
+}
+
+#[test]
+fn with_size() {
+    for default in [b"A".as_slice(), b"BC", b"DEF", b"GHIJ"] {
+        let sublayer = SubLayer::with_size(default, 4, 4);
+        assert_eq!(sublayer.cell_size() as usize, default.len());
+        assert_eq!((sublayer.width(), sublayer.height()), (4, 4));
+        assert_eq!(sublayer.default_value(), default);
+        assert_eq!(sublayer.data().len(), 16 * sublayer.cell_size() as usize);
+        assert!(sublayer.iter().all(|cell| cell == default));
+    }
+    let truncated = SubLayer::with_size(b"KLMNO", 1, 2);
+    assert_eq!(truncated.default_value(), b"KLMN");
+    assert_eq!(truncated.data(), b"KLMNKLMN");
+}
+
+#[test]
+fn checked_set() {
+    let mut layer = Layer::new();
+    layer.resize(3, 2);
+    assert_eq!(layer.set((2, 1), Tile::from_id(7)), Ok(()));
+    assert_eq!(layer[(2, 1)].id(), 7);
+    let err = layer.set((3, 0), Tile::from_id(7)).unwrap_err();
+    assert_eq!(
+        err,
+        OutOfBounds {
+            x: 3,
+            y: 0,
+            width: 3,
+            height: 2
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        "position (3, 0) is out of bounds of a 3x2 grid"
+    );
+    assert!(layer.set((0, 2), Tile::from_id(7)).is_err());
+
+    let sublayer = layer.add_sublayer(b"\0\0");
+    assert_eq!(sublayer.set((1, 1), b"OK"), Ok(()));
+    assert_eq!(&sublayer[(1, 1)], b"OK");
+    assert_eq!(
+        sublayer.set((1, 1), b"BAD"),
+        Err(SubLayerSetError::WrongLength {
+            expected: 2,
+            actual: 3
+        })
+    );
+    assert!(matches!(
+        sublayer.set((0, 5), b"NO"),
+        Err(SubLayerSetError::OutOfBounds(OutOfBounds { y: 5, .. }))
+    ));
+    assert_eq!(&sublayer[(1, 1)], b"OK");
+}
+
+#[test]
+fn iter_cells() {
+    let mut sublayer = SubLayer::with_size(b"\0", 4, 3);
+    for ((x, y), cell) in sublayer.iter_cells_mut() {
+        cell[0] = (x * 10 + y) as u8;
+    }
+    let mut expected = 0;
+    for y in 0..3 {
+        for x in 0..4 {
+            expected += sublayer.get_u8((x, y)).unwrap() as u32;
+        }
+    }
+    let sum = sublayer
+        .iter_cells()
+        .map(|(_, cell)| cell[0] as u32)
+        .sum::<u32>();
+    assert_eq!(sum, expected);
+    let cells = sublayer.iter_cells().collect::<Vec<_>>();
+    assert_eq!(cells[5], ((1, 1), [11].as_slice()));
+
+    let wide = SubLayer::with_size(b"abc", 2, 2);
+    assert!(wide.iter_cells().all(|(_, cell)| cell == b"abc"));
+
+    let mut empty_cells = SubLayer::with_size(b"", 2, 3);
+    assert_eq!(empty_cells.iter_cells().count(), 6);
+    assert!(empty_cells
+        .iter_cells_mut()
+        .all(|(_, cell)| cell.is_empty()));
+    assert_eq!(
+        empty_cells.iter_cells().last(),
+        Some(((1, 2), [].as_slice()))
+    );
+}
+
+#[test]
+fn link_indices() {
+    let mut link = SubLayerLink::default();
+    assert_eq!(link.tileset_index(), None);
+    assert_eq!(link.animation_index(), None);
+    assert_eq!(link.animation_frame_index(), None);
+
+    link.set_tileset_link(Some(0));
+    link.set_animation_link(Some(2));
+    link.set_animation_frame_link(Some(0xFF));
+    assert_eq!(link.tileset, 0);
+    assert_eq!(link.tileset_index(), Some(0));
+    assert_eq!(link.animation_index(), Some(2));
+    assert_eq!(link.animation_frame_index(), None);
+
+    link.set_tileset_link(None);
+    assert_eq!(link.tileset, SubLayerLink::UNLINKED);
+    assert_eq!(link.tileset_index(), None);
+}
+
+#[test]
+fn clone_tiles_only() {
+    let mut layer = Layer::builder().size(3, 2).opacity(0.25).build();
+    layer[(1, 1)] = Tile::from(0x0102);
+    layer.add_sublayer(&[7; 2]);
+    layer.sublayer_link.tileset = 0;
+    let clone = layer.clone_tiles_only();
+    assert!(clone.iter().eq(layer.iter()));
+    assert_eq!((clone.width(), clone.height()), (3, 2));
+    assert_eq!(clone.opacity, 0.25);
+    assert!(clone.sublayers.is_empty());
+    assert_eq!(clone.sublayer_link, SubLayerLink::default());
+}
+
+#[test]
+fn set_default_preserving() {
+    let mut sublayer = SubLayer::with_size(&[0; 4], 3, 2);
+    sublayer[(1, 0)].copy_from_slice(&[1, 2, 3, 4]);
+    sublayer[(2, 1)].copy_from_slice(&[5, 0, 0, 6]);
+    sublayer[(0, 1)].copy_from_slice(&[7, 8, 0, 0]);
+    assert_eq!(sublayer.set_default_preserving(&[0, 0]), [(1, 0), (2, 1)]);
+    assert_eq!(&sublayer[(1, 0)], &[1, 2]);
+    assert_eq!(&sublayer[(2, 1)], &[5, 0]);
+    assert_eq!(&sublayer[(0, 1)], &[7, 8]);
+    assert_eq!(sublayer.set_default_preserving(&[0; 3]), []);
+    assert_eq!(&sublayer[(1, 0)], &[1, 2, 0]);
+}
+
+#[test]
+fn try_set_default() {
+    let mut sublayer = SubLayer::with_size(b"AB", 2, 2);
+    sublayer[(1, 1)].copy_from_slice(b"CD");
+    let before = sublayer.clone();
+    assert_eq!(
+        sublayer.try_set_default(b"12345"),
+        Err(DefaultTooLong { len: 5 })
+    );
+    assert_eq!(sublayer, before);
+
+    assert_eq!(sublayer.try_set_default(b"WXYZ"), Ok(()));
+    assert_eq!(sublayer.cell_size(), 4);
+    assert_eq!(sublayer.default_value(), b"WXYZ");
+    assert_eq!(&sublayer[(1, 1)], b"CD\0\0");
+}
+
+#[test]
+fn iter_sublayers() -> Result<(), Box<dyn std::error::Error>> {
+    let mut map = TileMap::read(FILE)?;
+    let indices = map
+        .iter_sublayers()
+        .map(|(layer, index, _)| (layer, index))
+        .collect::<Vec<_>>();
+    assert_eq!(indices, [(0, 0)]);
+
+    let mut layer = Layer::new();
+    layer.resize(2, 2);
+    layer.add_sublayer(b"A");
+    layer.add_sublayer(b"BC");
+    map.layers.insert(0, Layer::new());
+    map.layers.push(layer);
+    let indices = map
+        .iter_sublayers()
+        .map(|(layer, index, sublayer)| (layer, index, sublayer.cell_size()))
+        .collect::<Vec<_>>();
+    assert_eq!(indices, [(1, 0, 1), (2, 0, 1), (2, 1, 2)]);
+
+    for (_, _, sublayer) in map.iter_sublayers_mut() {
+        sublayer.set_default(b"Z");
+    }
+    assert!(map
+        .iter_sublayers()
+        .all(|(_, _, sublayer)| sublayer.default_value() == b"Z"));
+    Ok(())
+}
+
+#[test]
+fn add_sublayer_with_data() {
+    let mut layer = Layer::new();
+    layer.resize(8, 8);
+    let data = (0..64).collect::<Vec<u8>>();
+    let sublayer = layer.add_sublayer_with_data(&[0xFF], data.clone()).unwrap();
+    assert_eq!(sublayer.cell_size(), 1);
+    assert_eq!(sublayer.data(), data);
+    assert_eq!(&sublayer[(3, 2)], [19]);
+    assert!(layer.is_consistent());
+
+    assert_eq!(
+        layer.add_sublayer_with_data(&[0, 0], data),
+        Err(SubLayerShapeError::WrongLength {
+            expected: 128,
+            actual: 64
+        })
+    );
+    assert_eq!(
+        layer.add_sublayer_with_data(b"12345", Vec::new()),
+        Err(SubLayerShapeError::DefaultTooLong(DefaultTooLong {
+            len: 5
+        }))
+    );
+    assert_eq!(layer.sublayers.len(), 1);
+}
+
+#[test]
+fn area_overflow() -> Result<(), Box<dyn std::error::Error>> {
+    let mut layer = Layer::new();
+    layer.resize(2, 2);
+    layer.add_sublayer(b"\x01\x02");
+    let overflow = AreaOverflow {
+        width: 0x10000,
+        height: 0x10000,
+    };
+    assert_eq!(layer.try_resize(0x10000, 0x10000), Err(overflow));
+    assert_eq!((layer.width(), layer.height()), (2, 2));
+    assert_eq!(layer.sublayers[0].data().len(), 8);
+    assert_eq!(
+        layer.sublayers[0].try_resize(0x10000, 0x10000),
+        Err(overflow)
+    );
+    layer.try_resize(3, 1)?;
+    assert_eq!(layer.sublayers[0].data().len(), 6);
+    assert_eq!(overflow.to_string(), "area of 65536x65536 overflows a u32");
+    assert_eq!(
+        Layer::builder().size(0x10000, 0x10000).try_build(),
+        Err(overflow)
+    );
+
+    // A file claiming a huge layer errors at its size, before anything else is read for it
+    let mut bytes = FILE.to_vec();
+    let layers = bytes.windows(4).position(|id| id == b"LAYR").unwrap();
+    bytes[layers + 10..layers + 18].copy_from_slice(&[0, 0, 1, 0, 0, 0, 1, 0]);
+    let size = layers as u64 + 10;
+    assert!(matches!(
+        TileMap::from_bytes(&bytes),
+        Err(ReadError::InvalidLayerLength { offset }) if offset == size
+    ));
+    assert!(matches!(
+        TileMap::read_header_only(std::io::Cursor::new(&bytes)),
+        Err(ReadError::InvalidLayerLength { offset }) if offset == size
+    ));
+    Ok(())
+}
+
+#[test]
+#[should_panic(expected = "layer area overflows a u32")]
+fn resize_overflow_panics() {
+    Layer::new().resize(0x10000, 0x10000);
+}
+
+#[test]
+#[should_panic(expected = "sublayer area overflows a u32")]
+fn sublayer_resize_overflow_panics() {
+    // This would otherwise try to allocate the whole area at once
+    SubLayer::with_size(&[0], 2, 2).resize(0x10000, 0x10000);
+}
+
+#[test]
+fn skip_sublayers() -> Result<(), Box<dyn std::error::Error>> {
+    let options = ReadOptions {
+        skip_sublayers: true,
+        ..ReadOptions::default()
+    };
+    let map = TileMap::read_with_options(FILE, &options)?;
+    let full = TileMap::read(FILE)?;
+    assert_eq!(full.layers[0].sublayers.len(), 1);
+    assert!(map.layers[0].sublayers.is_empty());
+    assert!(map.layers[0].same_tiles(&full.layers[0]));
+    assert_eq!(map.tilesets, full.tilesets);
+    assert_eq!(map.properties, full.properties);
+
+    // The skipped data is still there to see as raw blocks
+    let (_, raw) = TileMap::read_with_raw_blocks(FILE, &options)?;
+    let (_, full_raw) = TileMap::read_with_raw_blocks(FILE, &ReadOptions::default())?;
+    assert_eq!(raw, full_raw);
+    Ok(())
+}
+
+#[test]
+fn shrink_to_fit() {
+    let mut sublayer = SubLayer::with_size(&[1, 2], 8, 8);
+    sublayer.set_u16((3, 1), 0x0403);
+    sublayer.resize(8, 2);
+    // Comparisons only look at the cells, not what the buffer has left over from resizing
+    let mut expected = SubLayer::with_size(&[1, 2], 8, 2);
+    expected.set_u16((3, 1), 0x0403);
+    assert_eq!(sublayer, expected);
+
+    sublayer.shrink_to_fit();
+    assert_eq!(sublayer, expected);
+    assert_eq!(sublayer.data().len(), 8 * 2 * 2);
+    assert_eq!(sublayer.get_u16((3, 1)), Some(0x0403));
+    assert_eq!(sublayer.get((0, 0)), Some([1, 2].as_slice()));
+}
@@ -0,0 +1,150 @@
+use ct_tilemap::{CompressionLevel, Layer, Property, Tile, TileMap, TileView, WriteOptions};
+use std::collections::HashSet;
+
+mod common;
+use common::FILE;
+
+#[test]
+fn constructors() {
+    const TILE: Tile = Tile::from_id(0x1234);
+    assert_eq!(TILE, Tile { id: 0x1234 });
+    assert_eq!(Tile::from(0x1234), TILE);
+    assert_eq!(
+        Tile::from_position([5, 3]).position(),
+        Tile { position: [5, 3] }.position()
+    );
+    assert_eq!(Tile::EMPTY, Tile::default());
+    assert!(Tile::EMPTY.is_empty());
+    assert!(Tile::from_id(0xFFFF).is_empty());
+    assert!(!TILE.is_empty());
+}
+
+#[test]
+fn hash() {
+    let mut tiles = HashSet::new();
+    tiles.insert(Tile::from_id(0x0102));
+    tiles.insert(Tile::from(0x0102));
+    assert_eq!(tiles.len(), 1);
+
+    let mut layer = Layer::new();
+    layer.resize(2, 2);
+    let mut layers = HashSet::new();
+    layers.insert(layer.clone());
+    layers.insert(layer.clone());
+    assert_eq!(layers.len(), 1);
+    layer.opacity = f32::NAN;
+    layers.insert(layer.clone());
+    assert!(layers.contains(&layer));
+    assert_eq!(layers.len(), 2);
+
+    let properties = [
+        Property::Float(-0.0),
+        Property::Float(0.0),
+        Property::Integer(0),
+    ]
+    .into_iter()
+    .collect::<HashSet<_>>();
+    assert_eq!(properties.len(), 3);
+}
+
+#[test]
+fn view() {
+    let tile = Tile::with_position_be([5, 3]);
+    assert_eq!(tile.id(), 0x0503);
+    assert_eq!(
+        tile.view(),
+        TileView {
+            id: 0x0503,
+            position: [5, 3]
+        }
+    );
+    assert_eq!(Tile::from_id(0x0503).view(), tile.view());
+    // The union itself follows the host's byte order
+    let raw = if cfg!(target_endian = "big") {
+        [5, 3]
+    } else {
+        [3, 5]
+    };
+    assert_eq!(tile.position(), raw);
+    assert_eq!(Tile::from_position(raw).view().position, [5, 3]);
+}
+
+#[test]
+fn tile_coords() {
+    let tile = Tile::from_tile_coords(5, 3);
+    assert_eq!(tile.tile_coords(), (5, 3));
+    assert_eq!(tile.id(), 0x0503);
+    assert_eq!(tile.view(), Tile::with_position_be([5, 3]).view());
+    assert_eq!(Tile::from_id(0x1234).tile_coords(), (0x12, 0x34));
+    assert_eq!(Tile::EMPTY.tile_coords(), (0xFF, 0xFF));
+}
+
+#[test]
+fn remap_ids() -> Result<(), Box<dyn std::error::Error>> {
+    let mut map = TileMap::read(FILE)?;
+    let before = map.layers[0].iter().map(Tile::id).collect::<Vec<_>>();
+    assert!(before.contains(&0xFFFF));
+    map.remap_all(|id| if id == 0xFFFF { id } else { id + 1 });
+    let after = map.layers[0].iter().map(Tile::id).collect::<Vec<_>>();
+    for (old, new) in before.iter().zip(&after) {
+        if *old == 0xFFFF {
+            assert_eq!(*new, 0xFFFF);
+        } else {
+            assert_eq!(*new, old + 1);
+        }
+    }
+    // The positions follow the IDs, rather than being byte swapped
+    let tile = map.layers[0].iter().find(|tile| !tile.is_empty()).copied();
+    let tile = tile.expect("the sample has tiles");
+    assert_eq!(tile.view().id, tile.id());
+    assert_eq!(tile.view().position, tile.id().to_be_bytes());
+
+    // Empty tiles only change if the function changes them
+    let mut layer = Layer::new();
+    layer.resize(2, 1);
+    layer.remap_ids(|id| id.wrapping_add(1));
+    assert!(layer.iter().all(|tile| tile.id() == 0));
+    Ok(())
+}
+
+#[test]
+fn file_byte_order() -> Result<(), Box<dyn std::error::Error>> {
+    let mut map = TileMap::new();
+    let mut layer = Layer::new();
+    layer.resize(2, 1);
+    layer[(0, 0)] = Tile::from_id(0x1234);
+    layer[(1, 0)] = Tile::with_position_be([0x56, 0x78]);
+    map.layers.push(layer);
+
+    // Without compression, the tiles show up in the file as-is
+    let mut bytes = Vec::new();
+    map.write_with_options(
+        &mut bytes,
+        &WriteOptions {
+            compression: CompressionLevel::None,
+            ..WriteOptions::default()
+        },
+    )?;
+    assert!(bytes
+        .windows(4)
+        .any(|window| window == [0x12, 0x34, 0x56, 0x78]));
+
+    let read = TileMap::read(bytes.as_slice())?;
+    assert_eq!(read, map);
+    assert_eq!(read.layers[0][(0, 0)].view().position, [0x12, 0x34]);
+    assert_eq!(read.layers[0][(1, 0)].id(), 0x5678);
+    Ok(())
+}
+
+#[test]
+fn debug_position() {
+    let tile = Tile::from_tile_coords(0x0A, 3);
+    assert_eq!(format!("{:?}", tile.debug_position()), "0A03 (pos [10, 3])");
+    // The usual formatting stays compact
+    assert_eq!(format!("{tile:?}"), "Tile(0A03)");
+    assert_eq!(format!("{tile}"), "0A03");
+    assert_eq!(
+        format!("{:?}", Tile::EMPTY.debug_position()),
+        "FFFF (pos [255, 255])"
+    );
+}
@@ -0,0 +1,207 @@
+use const_str::concat_bytes;
+use ct_tilemap::{Layer, ParseColorError, TileMap, TileSet, TooManyTilesets};
+
+mod common;
+use common::FILE;
+
+#[test]
+fn hex_colors() -> Result<(), Box<dyn std::error::Error>> {
+    let tileset = TileSet::from_hex(String::from("overworld.png"), "#DA8972")?;
+    assert_eq!(tileset.transparent_color, (0xDA, 0x89, 0x72));
+    assert_eq!(tileset.transparent_rgb(), 0xDA8972);
+    assert_eq!(tileset.to_string(), "overworld.png #DA8972");
+    assert_eq!(
+        TileSet::from_hex(String::from("overworld.png"), "da8972")?,
+        tileset
+    );
+
+    let mut repacked = TileSet::new();
+    repacked.set_transparent_rgb(0xFFDA8972);
+    assert_eq!(repacked.transparent_color, tileset.transparent_color);
+
+    // The file stores the color as xBGR
+    let mut map = TileMap::new();
+    map.tilesets.push(tileset);
+    let bytes = map.to_bytes()?;
+    assert!(bytes.windows(4).any(|w| w == [0x00, 0x72, 0x89, 0xDA]));
+    assert_eq!(TileMap::read(bytes.as_slice())?, map);
+
+    assert_eq!(
+        TileSet::from_hex(String::new(), "#DA897"),
+        Err(ParseColorError::InvalidLength(5))
+    );
+    assert_eq!(
+        TileSet::from_hex(String::new(), "+DA8972"),
+        Err(ParseColorError::InvalidDigit('+'))
+    );
+    assert_eq!(
+        TileSet::from_hex(String::new(), "##DA8972"),
+        Err(ParseColorError::InvalidDigit('#'))
+    );
+    Ok(())
+}
+
+#[test]
+fn layer_tilesets() -> Result<(), Box<dyn std::error::Error>> {
+    let mut map = TileMap::read(FILE)?;
+    let paths = map.tileset_paths().collect::<Vec<_>>();
+    assert_eq!(paths, [(0, "overworld.png"), (1, "cave.png")]);
+
+    // The sample layer's tileset is unset
+    assert_eq!(map.layers[0].tileset, 0xFF);
+    assert_eq!(map.tileset_for_layer(&map.layers[0]), None);
+    map.layers[0].tileset = 1;
+    assert_eq!(
+        map.tileset_for_layer(&map.layers[0])
+            .map(|tileset| tileset.path.as_str()),
+        Some("cave.png")
+    );
+    let mut layer = Layer::new();
+    layer.tileset = 2;
+    assert_eq!(map.tileset_for_layer(&layer), None);
+    Ok(())
+}
+
+#[test]
+fn sort_by_path() -> Result<(), Box<dyn std::error::Error>> {
+    let mut map = TileMap::read(FILE)?;
+    // "overworld.png" comes before "cave.png" in the sample
+    map.layers[0].tileset = 1;
+    map.layers[0].sublayer_link.tileset = 0;
+    for cell in map.layers[0].sublayers[0].iter_mut() {
+        cell.copy_from_slice(&[1]);
+    }
+    map.layers[0].sublayers[0][(0, 0)].copy_from_slice(&[0]);
+    map.layers[0].sublayers[0][(1, 0)].copy_from_slice(&[0xFF]);
+    let mut unset = Layer::new();
+    unset.tileset = 0xFF;
+    map.layers.push(unset);
+
+    map.sort_tilesets_by_path();
+    let paths = map.tileset_paths().collect::<Vec<_>>();
+    assert_eq!(paths, [(0, "cave.png"), (1, "overworld.png")]);
+    assert_eq!(map.layers[0].tileset, 0);
+    assert_eq!(
+        map.tileset_for_layer(&map.layers[0])
+            .map(|tileset| tileset.path.as_str()),
+        Some("cave.png")
+    );
+    let sublayer = &map.layers[0].sublayers[0];
+    assert_eq!(&sublayer[(0, 0)], &[1]);
+    assert_eq!(&sublayer[(1, 0)], &[0xFF]);
+    assert_eq!(&sublayer[(2, 0)], &[0]);
+    assert_eq!(map.layers[1].tileset, 0xFF);
+    Ok(())
+}
+
+#[test]
+fn append() -> Result<(), Box<dyn std::error::Error>> {
+    let mut map = TileMap::read(FILE)?;
+    map.layers[0].tileset = 1;
+    let mut other = TileMap::new();
+    other.tilesets.push(TileSet {
+        path: String::from("dungeon.png"),
+        ..TileSet::default()
+    });
+    let mut layer = Layer::builder().size(2, 2).tileset(0).build();
+    layer.add_sublayer(&[0]);
+    layer.sublayers[0][(1, 1)].copy_from_slice(&[0xFF]);
+    layer.sublayer_link.tileset = 0;
+    other.layers.push(layer);
+    other.set_int("Integer", 5);
+    other.set_int("Width", 2);
+
+    let collisions = map.clone().append(other.clone(), false)?;
+    assert_eq!(collisions, ["Integer"]);
+
+    let collisions = map.append(other, true)?;
+    assert_eq!(collisions, ["Integer"]);
+    assert_eq!(map.layers.len(), 2);
+    assert_eq!(map.layers[0].tileset, 1);
+    assert_eq!(map.layers[1].tileset, 2);
+    assert_eq!(
+        map.tileset_for_layer(&map.layers[1])
+            .map(|tileset| tileset.path.as_str()),
+        Some("dungeon.png")
+    );
+    let sublayer = &map.layers[1].sublayers[0];
+    assert_eq!(&sublayer[(0, 0)], &[2]);
+    assert_eq!(&sublayer[(1, 1)], &[0xFF]);
+    assert_eq!(map.get_int("Integer"), Some(196));
+    assert_eq!(map.get_int("Width"), Some(2));
+    Ok(())
+}
+
+#[test]
+fn append_too_many_tilesets() -> Result<(), Box<dyn std::error::Error>> {
+    let mut map = TileMap::read(FILE)?;
+    let mut other = TileMap::new();
+    other.tilesets = vec![TileSet::new(); 254];
+    other
+        .layers
+        .push(Layer::builder().size(1, 1).tileset(253).build());
+    let before = map.clone();
+    let err = map.append(other.clone(), true).unwrap_err();
+    assert_eq!(err, TooManyTilesets { count: 256 });
+    // Nothing is moved over when it fails
+    assert_eq!(map, before);
+
+    // Dropping the other tilemap's tilesets instead is fine
+    map.append(other.clone(), false)?;
+    assert_eq!(map.tilesets.len(), 2);
+    assert_eq!(map.layers[1].tileset, 253);
+
+    // Exactly as many as can be stored is fine too
+    let mut map = before;
+    other.tilesets.pop();
+    other.layers[0].tileset = 252;
+    other
+        .layers
+        .push(Layer::builder().size(1, 1).tileset(253).build());
+    map.append(other, true)?;
+    assert_eq!(map.tilesets.len(), 255);
+    assert_eq!(map.layers[1].tileset, 254);
+    // This was already out of range, and shifting it would go past what can be stored
+    assert_eq!(map.layers[2].tileset, 0xFF);
+    Ok(())
+}
+
+const INVALID_UTF8: &[u8] = concat_bytes!(
+    b"ACHTUNG!",          // Magic string
+    b"\x05\x01",          // Version 5
+    b"MAP ",              // Property mapping
+    13_u32.to_le_bytes(), // Block length
+    1_u16.to_le_bytes(),  // Number of properties
+    4,
+    b"Key\xFF!", // Property 1
+    0,
+    1_i32.to_le_bytes(),  // Integer
+    b"TILE",              // Tilesets
+    16_u32.to_le_bytes(), // Block length
+    1,                    // Number of tilesets
+    b"\x00\xda\x89\x72",  // Transparent color
+    9,
+    b"tiles\xFF.png", // Path
+);
+
+#[test]
+fn original_bytes() -> Result<(), Box<dyn std::error::Error>> {
+    let mut map = TileMap::read(INVALID_UTF8)?;
+    let path = map.tilesets[0].path.clone();
+    assert_eq!(path, "tiles\u{FFFD}.png");
+    assert_eq!(map.original_bytes(&path), b"tiles\xFF.png");
+    assert_eq!(map.original_bytes("Key\u{FFFD}!"), b"Key\xFF!");
+    assert_eq!(map.get_int("Key\u{FFFD}!"), Some(1));
+
+    // The original bytes are written back, not the replacement characters
+    assert_eq!(map.to_bytes()?, INVALID_UTF8);
+    assert_eq!(TileMap::from_bytes(&map.to_bytes()?)?, map);
+
+    // Changing the path writes the new one
+    map.tilesets[0].path = String::from("tiles.png");
+    assert_eq!(map.original_bytes("tiles.png"), b"tiles.png");
+    let bytes = map.to_bytes()?;
+    assert!(!bytes.windows(10).any(|window| window == b"tiles\xFF.png"));
+    assert_eq!(TileMap::from_bytes(&bytes)?.tilesets[0].path, "tiles.png");
+    Ok(())
+}
@@ -0,0 +1,104 @@
+use const_str::concat_bytes;
+use ct_tilemap::{ReadError, ReadOptions, TileMap};
+use std::io::Cursor;
+
+const UNKNOWN_BLOCKS: &[u8] = concat_bytes!(
+    b"ACHTUNG!",         // Magic string
+    b"\x05\x01",         // Version 5
+    b"EXT1",             // Unknown block
+    5_u32.to_le_bytes(), // Block length
+    b"Hello",
+    b"TILE",              // Tilesets
+    15_u32.to_le_bytes(), // Block length
+    1,                    // Number of tilesets
+    0,
+    0xda,
+    0x89,
+    0x72, // xBGR color
+    8,
+    b"tiles.png",
+    b"EXT2",             // Unknown block
+    0_u32.to_le_bytes(), // Block length
+);
+
+const TRUNCATED_BLOCK: &[u8] = concat_bytes!(
+    b"ACHTUNG!",           // Magic string
+    b"\x05\x01",           // Version 5
+    b"EXT1",               // Unknown block
+    500_u32.to_le_bytes(), // Block length
+    b"Hello",
+);
+
+#[test]
+fn unknown_blocks() -> Result<(), Box<dyn std::error::Error>> {
+    let map = TileMap::read(UNKNOWN_BLOCKS)?;
+    assert_eq!(
+        map.unknown_blocks,
+        [(*b"EXT1", b"Hello".to_vec()), (*b"EXT2", Vec::new())]
+    );
+    assert_eq!(map.tilesets.len(), 1);
+
+    // Unknown blocks are moved to after the known ones
+    let mut buf = Cursor::new(Vec::new());
+    map.write(&mut buf)?;
+    assert!(buf.get_ref().ends_with(concat_bytes!(
+        b"EXT1",
+        5_u32.to_le_bytes(),
+        b"Hello",
+        b"EXT2",
+        0_u32.to_le_bytes()
+    )));
+    buf.set_position(0);
+    assert_eq!(TileMap::read(buf)?, map);
+
+    let strict = ReadOptions {
+        strict: true,
+        ..ReadOptions::default()
+    };
+    assert!(matches!(
+        TileMap::read_with_options(UNKNOWN_BLOCKS, &strict).unwrap_err(),
+        ReadError::InvalidHeader { header, offset: 10 } if header == "EXT1"
+    ));
+    assert!(matches!(
+        TileMap::read(TRUNCATED_BLOCK).unwrap_err(),
+        ReadError::UnexpectedEof {
+            block: Some([b'E', b'X', b'T', b'1']),
+            ..
+        }
+    ));
+    Ok(())
+}
+
+const ANIMATIONS: &[u8] = concat_bytes!(
+    b"ACHTUNG!",          // Magic string
+    b"\x05\x01",          // Version 5
+    b"MAP ",              // Property mapping
+    13_u32.to_le_bytes(), // Block length
+    1_u16.to_le_bytes(),  // Number of properties
+    4,
+    b"Speed", // Property 1
+    0,
+    3_i32.to_le_bytes(),  // Integer
+    b"ANIM",              // Animations, as emitted by older tools
+    15_u32.to_le_bytes(), // Block length
+    0,                    // Tileset
+    2_u16.to_le_bytes(),  // Number of frames
+    0x0102_u16.to_be_bytes(),
+    100_u32.to_le_bytes(), // Tile and duration of frame 1
+    0x0103_u16.to_be_bytes(),
+    250_u32.to_le_bytes(), // Tile and duration of frame 2
+);
+
+#[test]
+fn animation_blocks() -> Result<(), Box<dyn std::error::Error>> {
+    let map = TileMap::read(ANIMATIONS)?;
+    assert_eq!(map.get_int("Speed"), Some(3));
+    assert_eq!(map.unknown_blocks.len(), 1);
+    let (id, contents) = &map.unknown_blocks[0];
+    assert_eq!(id, b"ANIM");
+    assert_eq!(contents.len(), 15);
+
+    // The block is last already, so the whole file is written back the same
+    assert_eq!(map.to_bytes()?, ANIMATIONS);
+    Ok(())
+}
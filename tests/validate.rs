@@ -0,0 +1,71 @@
+use ct_tilemap::{Layer, Property, TileMap, TileSet, ValidationWarning};
+
+mod common;
+use common::FILE;
+
+#[test]
+fn valid() -> Result<(), Box<dyn std::error::Error>> {
+    let map = TileMap::read(FILE)?;
+    assert_eq!(map.validate(), Ok(()));
+    assert_eq!(TileMap::new().validate(), Ok(()));
+    Ok(())
+}
+
+#[test]
+fn warnings() {
+    let mut map = TileMap::new();
+    map.layers = vec![Layer::new(); 65536];
+    map.tilesets = vec![
+        TileSet {
+            path: "a".repeat(257),
+            ..TileSet::default()
+        },
+        TileSet::default(),
+    ];
+    map.tilesets.resize(256, TileSet::default());
+    map.properties.insert(String::new(), Property::Integer(0));
+    map.properties
+        .insert("b".repeat(257), Property::String(b"long".to_vec()));
+    map.properties
+        .insert(String::from("empty"), Property::String(Vec::new()));
+    for _ in 0..255 {
+        map.layers[3].add_sublayer(b"\0");
+    }
+    let warnings = map.validate().expect_err("map should not be valid");
+    assert_eq!(
+        warnings,
+        [
+            ValidationWarning::TooManyLayers(65536),
+            ValidationWarning::TooManyTilesets(256),
+            ValidationWarning::EmptyPropertyKey,
+            ValidationWarning::PropertyKeyTooLong("b".repeat(257)),
+            ValidationWarning::EmptyStringProperty(String::from("empty")),
+            ValidationWarning::SublayerCountExceeded {
+                layer: 3,
+                count: 255
+            },
+            ValidationWarning::TilesetPathTooLong(0),
+        ]
+        .into_iter()
+        .chain((1..256).map(ValidationWarning::EmptyTilesetPath))
+        .collect::<Vec<_>>()
+    );
+
+    // Older versions can store fewer layers
+    let mut map = TileMap::new();
+    map.version = 0;
+    map.layers = vec![Layer::new(); 256];
+    assert_eq!(
+        map.validate(),
+        Err(vec![ValidationWarning::TooManyLayers(256)])
+    );
+
+    let mut map = TileMap::new();
+    for i in 0..65536 {
+        map.properties.insert(i.to_string(), Property::Integer(i));
+    }
+    assert_eq!(
+        map.validate(),
+        Err(vec![ValidationWarning::TooManyProperties(65536)])
+    );
+}
@@ -0,0 +1,124 @@
+use ct_tilemap::{Layer, TileMap, TileSet, WriteError};
+use std::io::Cursor;
+
+mod common;
+use common::FILE;
+
+fn layered_map() -> TileMap {
+    let mut map = TileMap::new();
+    map.tilesets.push(TileSet {
+        path: "tiles.png".into(),
+        transparent_color: (0xDA, 0x89, 0x72),
+    });
+    for _ in 0..2 {
+        let mut layer = Layer::new();
+        layer.resize(4, 4);
+        layer.tile_dimensions = (8, 8);
+        map.layers.push(layer);
+    }
+    map
+}
+
+#[test]
+fn versioned_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+    let map = layered_map();
+    for version in 0..=5 {
+        let mut buf = Cursor::new(Vec::new());
+        map.write_versioned(&mut buf, version)?;
+        let written = buf.get_ref();
+        assert_eq!(&written[8..10], &[version as u8, 1]);
+        buf.set_position(0);
+        let mut same_map = TileMap::read(buf)?;
+        assert_eq!(same_map.version, version);
+        same_map.version = map.version;
+        assert_eq!(map, same_map, "Round-trip at version {version} failed!");
+    }
+    Ok(())
+}
+
+#[test]
+fn global_dimensions() -> Result<(), Box<dyn std::error::Error>> {
+    let map = layered_map();
+    let mut buf = Cursor::new(Vec::new());
+    map.write_versioned(&mut buf, 2)?;
+    // Version 2 still emits the deprecated block with the global dimensions
+    let written = buf.get_ref();
+    assert_eq!(&written[10..14], b"MAP ");
+    assert_eq!(&written[14..18], &4_u32.to_le_bytes());
+    assert_eq!(&written[18..22], &[8, 0, 8, 0]);
+    Ok(())
+}
+
+#[test]
+fn unrepresentable_data() {
+    let mut map = layered_map();
+    map.layers[1].tile_dimensions = (16, 16);
+    assert!(matches!(
+        map.write_versioned(Vec::new(), 1).unwrap_err(),
+        WriteError::Unrepresentable(1, _)
+    ));
+    assert!(map.write_versioned(Vec::new(), 2).is_ok());
+
+    let mut map = layered_map();
+    let _ = map.layers[0].add_sublayer(b"\0");
+    assert!(matches!(
+        map.write_versioned(Vec::new(), 3).unwrap_err(),
+        WriteError::Unrepresentable(3, _)
+    ));
+    assert!(map.write_versioned(Vec::new(), 4).is_ok());
+
+    let mut map = layered_map();
+    map.layers[0].sublayer_link.animation_frame = 0;
+    assert!(matches!(
+        map.write_versioned(Vec::new(), 4).unwrap_err(),
+        WriteError::Unrepresentable(4, _)
+    ));
+    assert!(map.write_versioned(Vec::new(), 5).is_ok());
+
+    let mut map = layered_map();
+    map.properties.insert("Foo".into(), 0.into());
+    assert!(matches!(
+        map.write_versioned(Vec::new(), 2).unwrap_err(),
+        WriteError::Unrepresentable(2, _)
+    ));
+    assert!(map.write_versioned(Vec::new(), 3).is_ok());
+
+    assert!(matches!(
+        map.write_versioned(Vec::new(), 6).unwrap_err(),
+        WriteError::UnsupportedVersion(6)
+    ));
+}
+
+#[test]
+fn write_uses_version() -> Result<(), Box<dyn std::error::Error>> {
+    let mut map = layered_map();
+    map.version = 3;
+    let mut buf = Cursor::new(Vec::new());
+    map.write(&mut buf)?;
+    assert_eq!(&buf.get_ref()[8..10], &[3, 1]);
+    buf.set_position(0);
+    assert_eq!(TileMap::read(buf)?, map);
+
+    map.version = 1;
+    let _ = map.layers[0].add_sublayer(b"\0");
+    map.write(Vec::new())
+        .expect_err("should have failed to write sublayers at version 1");
+    Ok(())
+}
+
+#[test]
+fn version_flags_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = FILE.to_vec();
+    assert_eq!(&file[8..10], b"\x05\x01");
+    file[9] = 0;
+    let map = TileMap::read(file.as_slice())?;
+    assert_eq!((map.version, map.version_flags), (5, 0));
+    let written = map.to_bytes()?;
+    assert_eq!(&written[8..10], b"\x05\x00");
+
+    let map = TileMap::read(FILE)?;
+    assert_eq!(map.version_flags, 1);
+    assert_eq!(&map.to_bytes()?[8..10], b"\x05\x01");
+    assert_eq!(TileMap::new().version_flags, 1);
+    Ok(())
+}